@@ -0,0 +1,1319 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use tui::style::Color;
+
+use crate::{
+    convert_millis_to_time, localtime::Date, Action, AfterVoid, Clock, Interruption,
+    InterruptionKind, MonotonicClock, Phase, PhaseKind, PhaseOutcome, PomoState, Settings,
+    Snapshot, TaskList, Transition,
+};
+
+/// The Pomodoro state machine.
+pub struct App<C: Clock = MonotonicClock> {
+    state: PomoState,
+    /// Full length of the running phase, including adjustments.
+    phase_time: i64,
+    settings: Settings,
+    /// Index of the running phase in the schedule.
+    cycle: Option<usize>,
+    clock: C,
+    last_update_time: Instant,
+    paused: bool,
+    /// Wall-clock time the running phase started at.
+    phase_started: SystemTime,
+    /// Time the running phase has spent paused.
+    paused_time: Duration,
+    /// Interruptions logged during the running phase.
+    interruptions: Vec<Interruption>,
+    transitions: Vec<Transition>,
+    /// Work phases completed since the last start.
+    pomodoros: u32,
+    /// Time spent in work phases since the last start.
+    focus_time: Duration,
+    /// Work phases completed on a day, see [`App::pomodoros_today`].
+    today: Option<(Date, u32)>,
+    tasks: TaskList,
+}
+
+impl<C: Clock> App<C> {
+    /// Creates an app sitting in the menu.
+    pub fn new(settings: Settings, clock: C) -> Self {
+        Self {
+            state: PomoState::Menu,
+            phase_time: 0,
+            settings,
+            cycle: None,
+            last_update_time: clock.now(),
+            phase_started: clock.system_time(),
+            clock,
+            paused: false,
+            paused_time: Duration::ZERO,
+            interruptions: Vec::new(),
+            transitions: Vec::new(),
+            pomodoros: 0,
+            focus_time: Duration::ZERO,
+            today: None,
+            tasks: TaskList::default(),
+        }
+    }
+
+    pub fn state(&self) -> &PomoState {
+        &self.state
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Replaces the settings, e.g. after picking another profile or reloading
+    /// the config file. Durations take effect from the next phase on, the
+    /// running phase only picks up its new name and colour.
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+        let phases = &self.settings.schedule.phases;
+        if self.cycle.is_some_and(|cycle| cycle >= phases.len()) {
+            self.cycle = Some(phases.len().saturating_sub(1));
+        }
+        let Some(new) = self.cycle.and_then(|cycle| phases.get(cycle)) else {
+            return;
+        };
+        match &mut self.state {
+            PomoState::Running { phase, .. } | PomoState::Flow { phase, .. }
+                if phase.kind == new.kind =>
+            {
+                phase.name = new.name.clone();
+                phase.color = new.color;
+            }
+            // Not started yet, so it is replaced outright unless it is a
+            // flowtime break sized by the work before it.
+            PomoState::Pending { next } if next.kind == new.kind => {
+                let duration = if self.settings.flowtime && next.kind == PhaseKind::Break {
+                    next.duration
+                } else {
+                    new.duration
+                };
+                *next = Phase {
+                    duration,
+                    ..new.clone()
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Index of the running (or pending) phase in `settings.schedule`,
+    /// `None` before the first start.
+    pub fn cycle(&self) -> Option<usize> {
+        self.cycle
+    }
+
+    /// Work phases completed since the last start.
+    pub fn pomodoros(&self) -> u32 {
+        self.pomodoros
+    }
+
+    /// Time spent in work phases since the last start, leaving out voided
+    /// ones.
+    pub fn focus_time(&self) -> Duration {
+        self.focus_time
+    }
+
+    /// Work phases completed today, by the day they started on like in
+    /// [`Stats`](crate::Stats), including those passed to
+    /// [`App::set_pomodoros_today`].
+    pub fn pomodoros_today(&self) -> u32 {
+        match self.today {
+            Some((date, count)) if date == Date::local(self.clock.system_time()) => count,
+            _ => 0,
+        }
+    }
+
+    /// Sets how many work phases were completed on `date` before the app
+    /// started, e.g. from the history file.
+    pub fn set_pomodoros_today(&mut self, date: Date, count: u32) {
+        self.today = Some((date, count));
+    }
+
+    /// Pomodoros completed today and `settings.daily_goal`, if there is one.
+    pub fn daily_progress(&self) -> Option<(u32, u32)> {
+        Some((self.pomodoros_today(), self.settings.daily_goal?))
+    }
+
+    /// The task list, completed work phases count towards its active task.
+    pub fn tasks(&self) -> &TaskList {
+        &self.tasks
+    }
+
+    pub fn tasks_mut(&mut self) -> &mut TaskList {
+        &mut self.tasks
+    }
+
+    pub fn set_tasks(&mut self, tasks: TaskList) {
+        self.tasks = tasks;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// (Re)starts at the first phase of the schedule. Does nothing if the
+    /// schedule is empty, see [`Options::validate`](crate::Options::validate).
+    pub fn start(&mut self) {
+        if self.settings.schedule.phases.is_empty() {
+            return;
+        }
+        self.set_state(self.begin(self.settings.schedule.phases[0].clone()));
+        self.cycle = Some(0);
+        self.pomodoros = 0;
+        self.focus_time = Duration::ZERO;
+        self.last_update_time = self.clock.now();
+    }
+
+    /// Adds `settings.adjust_step` to the running phase.
+    pub fn extend(&mut self) {
+        self.adjust(self.settings.adjust_step.as_millis() as i64);
+    }
+
+    /// Takes `settings.adjust_step` off the running phase, a phase shortened
+    /// past zero ends on the next update.
+    pub fn shorten(&mut self) {
+        self.adjust(-(self.settings.adjust_step.as_millis() as i64));
+    }
+
+    /// Changes the time left in the running phase by `delta` milliseconds.
+    pub fn adjust(&mut self, delta: i64) {
+        let Some(time_left) = self.state.get_inner() else {
+            return;
+        };
+        let new_time_left = (time_left + delta).max(time_left.min(0));
+        self.phase_time += new_time_left - time_left;
+        self.state = self.state.with_inner(new_time_left);
+    }
+
+    /// Counts down the running phase by the time elapsed since the last call
+    /// and moves on to the next phase when it runs out. With
+    /// `settings.overtime` the phase instead keeps counting into negative time
+    /// until [`App::confirm`] is called, and with
+    /// `settings.confirm_transitions` the next phase waits in
+    /// [`PomoState::Pending`] until it is confirmed. Once the session target
+    /// in `settings.sessions` or `settings.until` is reached the app moves to
+    /// [`PomoState::Complete`] instead.
+    pub fn update(&mut self) {
+        let time = self.clock.now();
+        let delta = time.saturating_duration_since(self.last_update_time);
+        self.last_update_time = time;
+        self.advance(delta);
+    }
+
+    /// Counts time that passed while the app wasn't being updated, e.g. while
+    /// the program was closed. A phase that would have run out in that time
+    /// is recorded as ending when it would have, and as nobody was there to
+    /// start the next one that waits in [`PomoState::Pending`] instead of
+    /// running through the rest of the schedule. Phases that only end when
+    /// the user says so, flowtime work and overtime, stop counting where the
+    /// user left them and the rest of the time away counts as paused.
+    pub fn catch_up(&mut self, elapsed: Duration) {
+        let now = self.clock.system_time();
+        let time_left = match self.state {
+            _ if self.paused => None,
+            PomoState::Flow { .. } => Some(0),
+            _ => self.state.get_inner(),
+        };
+        match time_left {
+            Some(time_left) if elapsed.as_millis() as i64 >= time_left => {
+                let until_out = Duration::from_millis(time_left.max(0) as u64);
+                if self.awaiting_end() || self.settings.overtime {
+                    self.advance(until_out);
+                    self.paused_time += elapsed - until_out;
+                } else {
+                    self.state = self.state.with_inner(0);
+                    let ran_out = now.checked_sub(elapsed).unwrap_or(now) + until_out;
+                    self.finish_phase(PhaseOutcome::Completed, ran_out, true);
+                }
+            }
+            _ => self.advance(elapsed),
+        }
+        self.last_update_time = self.clock.now();
+    }
+
+    /// Counts `delta` off the running phase.
+    fn advance(&mut self, delta: Duration) {
+        if self.paused {
+            if self.state.phase().is_some() {
+                self.paused_time += delta;
+            }
+            return;
+        }
+
+        if let PomoState::Flow { elapsed, .. } = &mut self.state {
+            *elapsed += delta;
+            return;
+        }
+
+        let inner_time = match self.state.get_inner() {
+            Some(i) => i,
+            _ => return,
+        };
+        let new_inner_time = inner_time - delta.as_millis() as i64;
+
+        // just update the timer
+        if new_inner_time.is_positive() || self.settings.overtime {
+            self.state = self.state.with_inner(new_inner_time);
+            return;
+        }
+
+        self.state = self.state.with_inner(0);
+        self.finish_phase(
+            PhaseOutcome::Completed,
+            self.clock.system_time(),
+            self.settings.confirm_transitions,
+        );
+    }
+
+    /// Ends the running phase early and moves on exactly as if it had run out.
+    /// A pending phase is started right away and a flowtime phase is ended as
+    /// completed. Does nothing in the menu or once the session is complete.
+    pub fn skip(&mut self) {
+        match self.state {
+            PomoState::Menu | PomoState::Complete => {}
+            PomoState::Pending { .. } => self.confirm(),
+            _ => {
+                let outcome = if self.awaiting_end() {
+                    PhaseOutcome::Completed
+                } else {
+                    PhaseOutcome::Skipped
+                };
+                self.finish_phase(
+                    outcome,
+                    self.clock.system_time(),
+                    self.settings.confirm_transitions,
+                );
+            }
+        }
+    }
+
+    /// Moves on from a phase that has run into overtime or a flowtime phase,
+    /// or starts the pending phase.
+    pub fn confirm(&mut self) {
+        if let PomoState::Pending { next } = &self.state {
+            let next = self.begin(next.clone());
+            if self.session_done() {
+                self.set_state(PomoState::Complete);
+            } else {
+                self.set_state(next);
+            }
+            self.last_update_time = self.clock.now();
+        } else if self.awaiting_end() {
+            self.skip();
+        }
+    }
+
+    /// Ends the running phase as [`PhaseOutcome::Abandoned`] and goes back to
+    /// the menu, e.g. before restarting or quitting. A pending phase that
+    /// hasn't started is dropped without being recorded.
+    pub fn abandon(&mut self) {
+        if self.state.phase().is_some() {
+            self.end_phase(PhaseOutcome::Abandoned, None, self.clock.system_time());
+        }
+        if self.state.is_active() {
+            self.set_state(PomoState::Menu);
+            self.cycle = None;
+            self.paused = false;
+        }
+    }
+
+    /// Calls off the running work phase, recording it as
+    /// [`PhaseOutcome::Voided`] with `reason`. Then either goes back to the
+    /// menu or starts the same work phase over, depending on
+    /// `settings.after_void`, without moving on in the schedule. Does nothing
+    /// and returns `false` unless the work phase that started at `started`
+    /// is still running.
+    pub fn void(&mut self, started: SystemTime, reason: Option<String>) -> bool {
+        if !self.is_working() || self.phase_started != started {
+            return false;
+        }
+        self.end_phase(PhaseOutcome::Voided, reason, self.clock.system_time());
+        self.paused = false;
+        let phase = self
+            .cycle
+            .and_then(|cycle| self.settings.schedule.phases.get(cycle))
+            .filter(|phase| phase.kind == PhaseKind::Work)
+            .cloned();
+        match (self.settings.after_void, phase) {
+            (AfterVoid::Work, Some(phase)) => {
+                self.set_state(self.begin(phase));
+                self.last_update_time = self.clock.now();
+            }
+            _ => {
+                self.set_state(PomoState::Menu);
+                self.cycle = None;
+            }
+        }
+        true
+    }
+
+    /// Wall-clock time the running phase started at.
+    pub fn phase_started(&self) -> SystemTime {
+        self.phase_started
+    }
+
+    /// Whether a work phase is running.
+    pub fn is_working(&self) -> bool {
+        self.state
+            .phase()
+            .is_some_and(|phase| phase.kind == PhaseKind::Work)
+    }
+
+    /// Logs an interruption of the running work phase and returns when it
+    /// was logged, `None` if there is no work phase to interrupt.
+    pub fn interrupt(&mut self, kind: InterruptionKind) -> Option<SystemTime> {
+        if !self.is_working() {
+            return None;
+        }
+        let at = self.clock.system_time();
+        self.interruptions.push(Interruption {
+            kind,
+            at,
+            note: None,
+        });
+        Some(at)
+    }
+
+    /// Sets the note of the interruption logged at `at`. Returns `false` if
+    /// its phase has ended in the meantime, as the interruption has been
+    /// recorded with it then.
+    pub fn note_interruption(&mut self, at: SystemTime, note: impl Into<String>) -> bool {
+        match self.interruptions.iter_mut().rev().find(|i| i.at == at) {
+            Some(interruption) => {
+                interruption.note = Some(note.into());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Interruptions logged during the running phase.
+    pub fn interruptions(&self) -> &[Interruption] {
+        &self.interruptions
+    }
+
+    /// Whether the running phase only ends when the user says so.
+    fn awaiting_end(&self) -> bool {
+        self.in_overtime() || matches!(self.state, PomoState::Flow { .. })
+    }
+
+    /// Whether the running phase has run out and is waiting for
+    /// [`App::confirm`].
+    pub fn in_overtime(&self) -> bool {
+        matches!(self.state.get_inner(), Some(time_left) if time_left <= 0)
+    }
+
+    /// Takes the phases that have ended since the last call, oldest first.
+    pub fn drain_transitions(&mut self) -> Vec<Transition> {
+        std::mem::take(&mut self.transitions)
+    }
+
+    /// The session as it is now, to be picked up later with
+    /// [`App::restore`].
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            saved_at: self.clock.system_time(),
+            fingerprint: self.settings.fingerprint(),
+            state: self.state.clone(),
+            phase_time: self.phase_time,
+            cycle: self.cycle,
+            paused: self.paused,
+            phase_started: self.phase_started,
+            paused_time: self.paused_time,
+            interruptions: self.interruptions.clone(),
+            pomodoros: self.pomodoros,
+            focus_time: self.focus_time,
+        }
+    }
+
+    /// Continues the session in `snapshot`, counting the wall-clock time since
+    /// it was taken with [`App::catch_up`].
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.state = snapshot.state;
+        self.phase_time = snapshot.phase_time;
+        self.cycle = snapshot.cycle;
+        self.paused = snapshot.paused;
+        self.phase_started = snapshot.phase_started;
+        self.paused_time = snapshot.paused_time;
+        self.interruptions = snapshot.interruptions;
+        self.pomodoros = snapshot.pomodoros;
+        self.focus_time = snapshot.focus_time;
+        self.last_update_time = self.clock.now();
+        // The schedule may have changed in the meantime.
+        self.set_settings(self.settings.clone());
+        let away = self
+            .clock
+            .system_time()
+            .duration_since(snapshot.saved_at)
+            .unwrap_or_default();
+        self.catch_up(away);
+    }
+
+    /// Records the end of the running phase at `ended` and moves on to the
+    /// next one, which waits in [`PomoState::Pending`] with
+    /// `wait_for_confirm`, or to [`PomoState::Complete`] when the session
+    /// target is reached.
+    fn finish_phase(&mut self, outcome: PhaseOutcome, ended: SystemTime, wait_for_confirm: bool) {
+        self.end_phase(outcome, None, ended);
+        if self.session_done() {
+            self.set_state(PomoState::Complete);
+            return;
+        }
+        let mut next = self.next_state();
+        if let (PomoState::Flow { elapsed, .. }, PhaseKind::Break) = (&self.state, next.kind) {
+            next.duration = self.settings.flow_breaks.break_for(*elapsed);
+        }
+        if wait_for_confirm {
+            self.state = PomoState::Pending { next };
+        } else {
+            self.set_state(self.begin(next));
+        }
+    }
+
+    /// State for starting `phase`, work phases count up in flowtime mode.
+    fn begin(&self, phase: Phase) -> PomoState {
+        if self.settings.flowtime && phase.kind == PhaseKind::Work {
+            PomoState::Flow {
+                phase,
+                elapsed: Duration::ZERO,
+            }
+        } else {
+            PomoState::running(phase)
+        }
+    }
+
+    fn session_done(&self) -> bool {
+        self.settings
+            .sessions
+            .is_some_and(|sessions| self.pomodoros >= sessions)
+            || self
+                .settings
+                .until
+                .is_some_and(|until| self.clock.system_time() >= until)
+    }
+
+    fn end_phase(&mut self, outcome: PhaseOutcome, reason: Option<String>, ended: SystemTime) {
+        let (planned, actual) = match &self.state {
+            PomoState::Running { phase, time_left } => (
+                Some(phase.duration),
+                Duration::from_millis((self.phase_time - time_left).max(0) as u64),
+            ),
+            PomoState::Flow { elapsed, .. } => (None, *elapsed),
+            _ => (None, Duration::ZERO),
+        };
+        let work = self
+            .state
+            .phase()
+            .is_some_and(|phase| phase.kind == PhaseKind::Work);
+        if work && outcome != PhaseOutcome::Voided {
+            self.focus_time += actual;
+            if outcome == PhaseOutcome::Completed {
+                self.pomodoros += 1;
+                self.tasks.attribute();
+                let date = Date::local(self.phase_started);
+                self.today = match self.today {
+                    Some((day, count)) if day == date => Some((day, count + 1)),
+                    Some((day, _)) if day > date => self.today,
+                    _ => Some((date, 1)),
+                };
+            }
+        }
+        self.transitions.push(Transition {
+            from: self.state.clone(),
+            outcome,
+            started: self.phase_started,
+            ended,
+            planned,
+            actual,
+            paused: self.paused_time,
+            overtime: Duration::from_millis(
+                self.state.get_inner().map_or(0, |t| (-t).max(0)) as u64
+            ),
+            task: work
+                .then(|| self.tasks.active().map(|task| task.name.clone()))
+                .flatten(),
+            interruptions: std::mem::take(&mut self.interruptions),
+            reason,
+        });
+    }
+
+    /// The phase following the running one, advancing `cycle`.
+    fn next_state(&mut self) -> Phase {
+        let phases = &self.settings.schedule.phases;
+        let next = self.cycle.map_or(0, |i| (i + 1) % phases.len());
+        self.cycle = Some(next);
+        phases[next].clone()
+    }
+
+    fn set_state(&mut self, state: PomoState) {
+        self.phase_time = state.get_inner().unwrap_or(0);
+        self.phase_started = self.clock.system_time();
+        self.paused_time = Duration::ZERO;
+        self.interruptions.clear();
+        self.state = state;
+    }
+
+    /// Label shown on the gauge.
+    pub fn get_state_text(&self) -> String {
+        if self.paused {
+            return "PAUSED".into();
+        };
+        match &self.state {
+            PomoState::Menu => {
+                let keys = [
+                    Action::Start,
+                    Action::Quit,
+                    Action::Pause,
+                    Action::Skip,
+                    Action::Profiles,
+                    Action::Tasks,
+                    Action::Stats,
+                    Action::Help,
+                ]
+                .into_iter()
+                .filter_map(|action| {
+                    let chord = self.settings.keys.hint(action)?;
+                    Some(format!("'{chord}' to {}", action.description()))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+                let mut text = String::new();
+                if let Some(profile) = &self.settings.profile {
+                    text.push_str(&format!("Profile: {profile} - "));
+                }
+                if let Some(goal) = self.goal_label() {
+                    text.push_str(&format!("{goal} - "));
+                }
+                text.push_str(&format!("Press {keys}"));
+                text
+            }
+            PomoState::Running { phase, time_left } => {
+                let mut text = format!("{}: {}", phase.name, format_time_left(*time_left));
+                if let Some((cycle, cycles)) = self.work_cycle() {
+                    text.push_str(&format!(" - Cycle {}/{}", cycle, cycles));
+                }
+                text.push_str(&self.task_label(phase));
+                text.push_str(&self.interruption_label());
+                if let Some(goal) = self.goal_label() {
+                    text.push_str(&format!(" - {goal}"));
+                }
+                text
+            }
+            PomoState::Flow { phase, elapsed } => {
+                let mut text = format!(
+                    "{}: {}",
+                    phase.name,
+                    convert_millis_to_time(elapsed.as_millis())
+                );
+                if let Some((cycle, cycles)) = self.work_cycle() {
+                    text.push_str(&format!(" - Cycle {}/{}", cycle, cycles));
+                }
+                text.push_str(&self.task_label(phase));
+                text.push_str(&self.interruption_label());
+                if let Some(goal) = self.goal_label() {
+                    text.push_str(&format!(" - {goal}"));
+                }
+                text.push_str(&self.hint(Action::Confirm, "take a break"));
+                text
+            }
+            PomoState::Pending { next }
+                if self.settings.flowtime && next.kind == PhaseKind::Work =>
+            {
+                format!(
+                    "Up next: {} (flow){}",
+                    next.name,
+                    self.hint(Action::Confirm, "start")
+                )
+            }
+            PomoState::Pending { next } => format!(
+                "Up next: {} ({}){}",
+                next.name,
+                convert_millis_to_time(next.duration.as_millis()),
+                self.hint(Action::Confirm, "start")
+            ),
+            PomoState::Complete => format!(
+                "Session complete: {} pomodoros, {} min focused{}",
+                self.pomodoros,
+                self.focus_time.as_secs() / 60,
+                self.hint(Action::Start, "start again")
+            ),
+        }
+    }
+
+    /// " - TASK" for a work phase with an active task.
+    fn task_label(&self, phase: &Phase) -> String {
+        match self.tasks.active() {
+            Some(task) if phase.kind == PhaseKind::Work => format!(" - {}", task.name),
+            _ => String::new(),
+        }
+    }
+
+    /// " - Interrupted: 2' 1-" once the running phase has been interrupted.
+    fn interruption_label(&self) -> String {
+        if self.interruptions.is_empty() {
+            return String::new();
+        }
+        let internal = self
+            .interruptions
+            .iter()
+            .filter(|i| i.kind == InterruptionKind::Internal)
+            .count();
+        let external = self.interruptions.len() - internal;
+        format!(" - Interrupted: {internal}' {external}-")
+    }
+
+    /// "6/10 today" when there is a daily goal.
+    fn goal_label(&self) -> Option<String> {
+        let (done, goal) = self.daily_progress()?;
+        Some(format!("{done}/{goal} today"))
+    }
+
+    /// " - press KEY to `what`", empty if `action` has no key.
+    fn hint(&self, action: Action, what: &str) -> String {
+        match self.settings.keys.hint(action) {
+            Some(chord) => format!(" - press '{chord}' to {what}"),
+            None => String::new(),
+        }
+    }
+
+    /// Which work phase of the schedule the timer is at, counting the breaks
+    /// after a work phase as part of it. `None` on the last phase when it is a
+    /// break, which rounds off the whole schedule.
+    fn work_cycle(&self) -> Option<(usize, usize)> {
+        let phases = &self.settings.schedule.phases;
+        let cycle = self.cycle?;
+        let cycles = self.settings.schedule.work_phases();
+        if cycles == 0 || (cycle + 1 == phases.len() && phases[cycle].kind == PhaseKind::Break) {
+            return None;
+        }
+        let done = phases[..=cycle]
+            .iter()
+            .filter(|phase| phase.kind == PhaseKind::Work)
+            .count();
+        Some((done.max(1), cycles))
+    }
+
+    /// Fraction of the running phase left, between 0 and 1, measured against
+    /// the phase length including any extensions. In overtime it is the
+    /// overtime so far relative to the phase length, capped at 1.
+    ///
+    /// Flowtime phases have no end, so their gauge fills up as
+    /// `elapsed / (elapsed + duration)`: half full after the phase's nominal
+    /// duration and approaching full from there.
+    pub fn get_ratio(&self) -> f64 {
+        if self.paused || self.state == PomoState::Complete {
+            return 1.;
+        };
+        if let PomoState::Flow { phase, elapsed } = &self.state {
+            let total = *elapsed + phase.duration.max(Duration::from_millis(1));
+            return elapsed.as_secs_f64() / total.as_secs_f64();
+        }
+        match self.state.get_inner() {
+            Some(time_left) if self.phase_time > 0 => {
+                (time_left.abs() as f64 / self.phase_time as f64).min(1.)
+            }
+            _ => 0.,
+        }
+    }
+
+    /// Gauge colour for the running phase.
+    pub fn get_color(&self) -> Color {
+        let ratio = self.get_ratio();
+        if self.in_overtime() {
+            return Color::Magenta;
+        }
+        match &self.state {
+            PomoState::Menu | PomoState::Pending { .. } => Color::Gray,
+            PomoState::Complete => Color::LightGreen,
+            PomoState::Flow { phase, .. } => phase.color.unwrap_or(Color::Rgb(
+                255 - (ratio * 255.) as u8,
+                (ratio * 255.) as u8,
+                0,
+            )),
+            PomoState::Running { phase, .. } => match (phase.color, phase.kind) {
+                (Some(color), _) => color,
+                (None, PhaseKind::Work) => {
+                    Color::Rgb((ratio * 255.) as u8, 255 - (ratio * 255.) as u8, 0)
+                }
+                (None, PhaseKind::Break) => Color::LightBlue,
+            },
+        }
+    }
+}
+
+/// Formats the time left in a phase, overtime is shown as `+MM:SS`.
+fn format_time_left(time_left: i64) -> String {
+    if time_left < 0 {
+        format!(
+            "+{}",
+            convert_millis_to_time(time_left.unsigned_abs() as u128)
+        )
+    } else {
+        convert_millis_to_time(time_left as u128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ManualClock;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    /// Two work phases per set, so the long break comes around quickly.
+    fn settings() -> Settings {
+        Settings::new(MINUTE * 25, MINUTE * 5, MINUTE * 20, 2, false)
+    }
+
+    fn app(settings: Settings) -> (App<ManualClock>, ManualClock) {
+        let clock = ManualClock::new();
+        (App::new(settings, clock.clone()), clock)
+    }
+
+    fn wait(app: &mut App<ManualClock>, clock: &ManualClock, minutes: u32) {
+        clock.advance(MINUTE * minutes);
+        app.update();
+    }
+
+    fn time_left(app: &App<ManualClock>) -> Option<Duration> {
+        app.state()
+            .get_inner()
+            .map(|time_left| Duration::from_millis(time_left as u64))
+    }
+
+    fn outcomes(app: &mut App<ManualClock>) -> Vec<(String, PhaseOutcome)> {
+        app.drain_transitions()
+            .into_iter()
+            .map(|transition| (transition.from.name().to_string(), transition.outcome))
+            .collect()
+    }
+
+    #[test]
+    fn voided_work_does_not_count() {
+        let (mut app, clock) = app(settings());
+        app.start();
+        wait(&mut app, &clock, 10);
+        app.interrupt(InterruptionKind::External);
+        assert!(app.void(app.phase_started(), Some("fire alarm".into())));
+        assert_eq!(app.state(), &PomoState::Menu);
+        assert_eq!(app.cycle(), None);
+        assert_eq!(app.focus_time(), Duration::ZERO);
+        assert_eq!(app.pomodoros(), 0);
+        let transition = &app.drain_transitions()[0];
+        assert_eq!(transition.outcome, PhaseOutcome::Voided);
+        assert_eq!(transition.reason.as_deref(), Some("fire alarm"));
+        assert_eq!(transition.interruptions.len(), 1);
+        assert_eq!(transition.actual, MINUTE * 10);
+    }
+
+    #[test]
+    fn counts_pomodoros_by_the_day_they_started() {
+        let tomorrow = Date::from_days(20_000);
+        let clock = ManualClock::starting_at(tomorrow.start() - MINUTE * 40);
+        let settings = Settings {
+            daily_goal: Some(5),
+            ..settings()
+        };
+        let mut app = App::new(settings.clone(), clock.clone());
+        // Counts from the history file are only used for their own day.
+        app.set_pomodoros_today(Date::from_days(19_998), 7);
+        assert_eq!(app.daily_progress(), Some((0, 5)));
+        app.start();
+        wait(&mut app, &clock, 25);
+        assert_eq!(app.daily_progress(), Some((1, 5)));
+
+        // The count starts over at midnight.
+        wait(&mut app, &clock, 5);
+        wait(&mut app, &clock, 15);
+        assert_eq!(app.pomodoros_today(), 0);
+        // The work phase from 23:50 to 00:15 counts for the day it started
+        // on, not for the one the history was read on after a restart.
+        let snapshot = app.snapshot();
+        let mut app = App::new(settings, clock.clone());
+        app.set_pomodoros_today(tomorrow, 2);
+        app.restore(snapshot);
+        wait(&mut app, &clock, 10);
+        assert_eq!(app.pomodoros(), 2);
+        assert_eq!(app.pomodoros_today(), 2);
+        wait(&mut app, &clock, 20);
+        wait(&mut app, &clock, 25);
+        assert_eq!(app.daily_progress(), Some((3, 5)));
+    }
+
+    #[test]
+    fn abandoning_records_the_time_so_far() {
+        let (mut app, clock) = app(settings());
+        app.start();
+        wait(&mut app, &clock, 10);
+        app.pause();
+        app.abandon();
+        assert_eq!(app.state(), &PomoState::Menu);
+        assert_eq!(app.cycle(), None);
+        assert!(!app.is_paused());
+        assert_eq!(app.focus_time(), MINUTE * 10);
+        assert_eq!(app.pomodoros(), 0);
+        let transition = &app.drain_transitions()[0];
+        assert_eq!(transition.outcome, PhaseOutcome::Abandoned);
+        assert_eq!(transition.planned, Some(MINUTE * 25));
+        assert_eq!(transition.actual, MINUTE * 10);
+
+        // A phase that hasn't started isn't recorded.
+        let (mut app, clock) = self::app(Settings {
+            confirm_transitions: true,
+            ..settings()
+        });
+        app.start();
+        wait(&mut app, &clock, 25);
+        assert!(matches!(app.state(), PomoState::Pending { .. }));
+        app.abandon();
+        assert_eq!(app.state(), &PomoState::Menu);
+        assert_eq!(app.cycle(), None);
+        assert_eq!(
+            outcomes(&mut app),
+            [("Work".to_string(), PhaseOutcome::Completed)]
+        );
+        // Nor is anything in the menu.
+        app.abandon();
+        assert!(app.drain_transitions().is_empty());
+    }
+
+    #[test]
+    fn notes_go_to_their_interruption() {
+        let (mut app, clock) = app(settings());
+        assert_eq!(app.interrupt(InterruptionKind::Internal), None);
+        app.start();
+        wait(&mut app, &clock, 5);
+        let first = app.interrupt(InterruptionKind::Internal).unwrap();
+        wait(&mut app, &clock, 5);
+        app.interrupt(InterruptionKind::External);
+        assert!(app.note_interruption(first, "email"));
+        let notes: Vec<_> = app
+            .interruptions()
+            .iter()
+            .map(|i| i.note.as_deref())
+            .collect();
+        assert_eq!(notes, [Some("email"), None]);
+
+        // Too late once the phase has been recorded.
+        let late = app.interrupt(InterruptionKind::External).unwrap();
+        wait(&mut app, &clock, 15);
+        assert!(!app.note_interruption(late, "phone"));
+        let transition = &app.drain_transitions()[0];
+        assert_eq!(transition.interruptions.len(), 3);
+        assert_eq!(transition.interruptions[2].note, None);
+    }
+
+    #[test]
+    fn voiding_can_restart_the_work_phase() {
+        let (mut app, clock) = app(Settings {
+            after_void: AfterVoid::Work,
+            ..settings()
+        });
+        app.start();
+        wait(&mut app, &clock, 25);
+        wait(&mut app, &clock, 5);
+        wait(&mut app, &clock, 10);
+        app.pause();
+        let voided = app.phase_started();
+        assert!(app.void(voided, None));
+        assert_eq!(app.state().name(), "Work");
+        assert_eq!(app.cycle(), Some(2));
+        assert_eq!(time_left(&app), Some(MINUTE * 25));
+        assert!(!app.is_paused());
+        assert_eq!(app.focus_time(), MINUTE * 25);
+        // Nor can the phase after the one that was meant.
+        wait(&mut app, &clock, 1);
+        assert!(!app.void(voided, None));
+        // Breaks can't be voided.
+        app.skip();
+        assert!(!app.void(app.phase_started(), None));
+        assert_eq!(app.state().name(), "Long break");
+        assert_eq!(app.drain_transitions().len(), 4);
+    }
+
+    #[test]
+    fn new_settings_keep_the_running_phase() {
+        let (mut app, clock) = app(settings());
+        app.start();
+        wait(&mut app, &clock, 25);
+        wait(&mut app, &clock, 2);
+        let mut reloaded = Settings {
+            dark_mode: true,
+            ..Settings::new(MINUTE * 50, MINUTE * 10, MINUTE * 30, 2, false)
+        };
+        reloaded.schedule.phases[1].name = "Stretch".into();
+        app.set_settings(reloaded);
+        assert_eq!(app.state().name(), "Stretch");
+        assert_eq!(time_left(&app), Some(MINUTE * 3));
+        assert_eq!(app.get_ratio(), 3. / 5.);
+        assert_eq!(app.cycle(), Some(1));
+        assert_eq!(app.pomodoros(), 1);
+        // The new durations apply from the next phase on.
+        wait(&mut app, &clock, 3);
+        assert_eq!(app.state().name(), "Work");
+        assert_eq!(app.cycle(), Some(2));
+        assert_eq!(time_left(&app), Some(MINUTE * 50));
+        assert_eq!(app.drain_transitions()[1].actual, MINUTE * 5);
+    }
+
+    /// An app picking up the session `app` left `minutes` ago.
+    fn resumed(app: &App<ManualClock>, clock: &ManualClock, minutes: u32) -> App<ManualClock> {
+        let snapshot = app.snapshot();
+        clock.advance(MINUTE * minutes);
+        let mut resumed = App::new(app.settings().clone(), clock.clone());
+        resumed.restore(snapshot);
+        resumed
+    }
+
+    #[test]
+    fn resumes_within_the_running_phase() {
+        let (mut app, clock) = app(settings());
+        app.start();
+        wait(&mut app, &clock, 10);
+        let mut app = resumed(&app, &clock, 5);
+        assert_eq!(app.state().name(), "Work");
+        assert_eq!(time_left(&app), Some(MINUTE * 10));
+        assert!(app.drain_transitions().is_empty());
+        wait(&mut app, &clock, 10);
+        let transition = &app.drain_transitions()[0];
+        assert_eq!(transition.actual, MINUTE * 25);
+        assert_eq!(transition.ended, transition.started + MINUTE * 25);
+    }
+
+    #[test]
+    fn resuming_after_the_phase_ran_out_waits_for_the_user() {
+        let (mut app, clock) = app(settings());
+        let started = clock.system_time();
+        app.start();
+        wait(&mut app, &clock, 10);
+        let mut app = resumed(&app, &clock, 3 * 60);
+        assert_eq!(
+            app.state(),
+            &PomoState::Pending {
+                next: settings().schedule.phases[1].clone()
+            }
+        );
+        // Only the phase that was running is recorded, ending when it ran out.
+        let transitions = app.drain_transitions();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].outcome, PhaseOutcome::Completed);
+        assert_eq!(transitions[0].started, started);
+        assert_eq!(transitions[0].ended, started + MINUTE * 25);
+        assert_eq!(app.pomodoros(), 1);
+        app.confirm();
+        assert_eq!(app.state().name(), "Short break");
+        assert_eq!(time_left(&app), Some(MINUTE * 5));
+
+        // A paused phase stays paused however long it was left.
+        app.pause();
+        let app = resumed(&app, &clock, 3 * 60);
+        assert!(app.is_paused());
+        assert_eq!(time_left(&app), Some(MINUTE * 5));
+    }
+
+    #[test]
+    fn resuming_flowtime_leaves_out_the_time_away() {
+        let (mut app, clock) = app(Settings {
+            flowtime: true,
+            ..settings()
+        });
+        app.start();
+        wait(&mut app, &clock, 10);
+        let mut app = resumed(&app, &clock, 10 * 60);
+        assert_eq!(
+            app.state(),
+            &PomoState::Flow {
+                phase: settings().schedule.phases[0].clone(),
+                elapsed: MINUTE * 10,
+            }
+        );
+        wait(&mut app, &clock, 5);
+        app.confirm();
+        assert_eq!(time_left(&app), Some(MINUTE * 3));
+        let transition = &app.drain_transitions()[0];
+        assert_eq!(transition.actual, MINUTE * 15);
+        assert_eq!(transition.paused, MINUTE * 10 * 60);
+        assert_eq!(app.focus_time(), MINUTE * 15);
+    }
+
+    #[test]
+    fn resuming_overtime_stops_where_the_phase_ran_out() {
+        let (mut app, clock) = app(Settings {
+            overtime: true,
+            ..settings()
+        });
+        app.start();
+        wait(&mut app, &clock, 10);
+        let mut app = resumed(&app, &clock, 3 * 60);
+        assert_eq!(app.state().get_inner(), Some(0));
+        assert!(app.in_overtime());
+        assert!(app.drain_transitions().is_empty());
+        wait(&mut app, &clock, 2);
+        app.confirm();
+        let transition = &app.drain_transitions()[0];
+        assert_eq!(transition.actual, MINUTE * 27);
+        assert_eq!(transition.overtime, MINUTE * 2);
+        assert_eq!(transition.paused, MINUTE * (3 * 60 - 15));
+
+        // Overtime already counted before leaving is kept, the time away isn't.
+        app.skip();
+        wait(&mut app, &clock, 30);
+        let mut app = resumed(&app, &clock, 60);
+        assert_eq!(app.state().name(), "Work");
+        assert_eq!(app.state().get_inner(), Some(-5 * 60 * 1000));
+        app.confirm();
+        assert_eq!(app.drain_transitions()[0].overtime, MINUTE * 5);
+    }
+
+    #[test]
+    fn skip_moves_on_like_expiry() {
+        let (mut skipped, _) = app(settings());
+        let (mut waited, clock) = app(settings());
+        skipped.start();
+        waited.start();
+        for (name, cycle, minutes) in [
+            ("Short break", 1, 25),
+            ("Work", 2, 5),
+            ("Long break", 3, 25),
+            ("Work", 0, 20),
+        ] {
+            skipped.skip();
+            wait(&mut waited, &clock, minutes);
+            for app in [&skipped, &waited] {
+                assert_eq!(app.state().name(), name);
+                assert_eq!(app.cycle(), Some(cycle));
+            }
+            assert_eq!(time_left(&skipped), time_left(&waited));
+        }
+        assert_eq!(
+            outcomes(&mut skipped)[..2],
+            [
+                ("Work".into(), PhaseOutcome::Skipped),
+                ("Short break".into(), PhaseOutcome::Skipped)
+            ]
+        );
+        assert_eq!(skipped.pomodoros(), 0);
+        assert_eq!(waited.pomodoros(), 2);
+    }
+
+    #[test]
+    fn adjusting_rescales_the_ratio() {
+        let (mut app, clock) = app(settings());
+        app.start();
+        wait(&mut app, &clock, 10);
+        assert_eq!(app.get_ratio(), 15. / 25.);
+        app.extend();
+        assert_eq!(time_left(&app), Some(MINUTE * 20));
+        assert_eq!(app.get_ratio(), 20. / 30.);
+        app.shorten();
+        app.shorten();
+        assert_eq!(app.get_ratio(), 10. / 20.);
+        // Shortening past zero ends the phase on the next update.
+        for _ in 0..3 {
+            app.shorten();
+        }
+        assert_eq!(time_left(&app), Some(Duration::ZERO));
+        app.update();
+        assert_eq!(app.state().name(), "Short break");
+        let transition = &app.drain_transitions()[0];
+        assert_eq!(transition.actual, MINUTE * 10);
+        assert_eq!(transition.planned, Some(MINUTE * 25));
+    }
+
+    #[test]
+    fn overtime_counts_past_zero_until_confirmed() {
+        let (mut app, clock) = app(Settings {
+            overtime: true,
+            ..settings()
+        });
+        app.start();
+        wait(&mut app, &clock, 27);
+        assert_eq!(app.state().get_inner(), Some(-2 * 60 * 1000));
+        assert!(app.in_overtime());
+        assert!(app.get_state_text().starts_with("Work: +02:00"));
+        assert_eq!(app.get_color(), Color::Magenta);
+        assert_eq!(app.get_ratio(), 2. / 25.);
+        app.confirm();
+        assert_eq!(app.state().name(), "Short break");
+        let transition = &app.drain_transitions()[0];
+        assert_eq!(transition.outcome, PhaseOutcome::Completed);
+        assert_eq!(transition.overtime, MINUTE * 2);
+        assert_eq!(transition.actual, MINUTE * 27);
+    }
+
+    #[test]
+    fn pending_phase_waits_for_confirm() {
+        let (mut app, clock) = app(Settings {
+            confirm_transitions: true,
+            ..settings()
+        });
+        app.start();
+        wait(&mut app, &clock, 25);
+        let PomoState::Pending { next } = app.state() else {
+            panic!("not pending: {:?}", app.state());
+        };
+        assert_eq!(next.name, "Short break");
+        assert!(app
+            .get_state_text()
+            .starts_with("Up next: Short break (05:00)"));
+        // Time spent waiting doesn't count towards the next phase.
+        wait(&mut app, &clock, 10);
+        assert!(matches!(app.state(), PomoState::Pending { .. }));
+        app.confirm();
+        assert_eq!(app.state().name(), "Short break");
+        wait(&mut app, &clock, 1);
+        assert_eq!(time_left(&app), Some(MINUTE * 4));
+    }
+
+    #[test]
+    fn skipped_phase_waits_for_confirm_too() {
+        let (mut app, _) = app(Settings {
+            confirm_transitions: true,
+            ..settings()
+        });
+        app.start();
+        app.skip();
+        assert_eq!(
+            app.state(),
+            &PomoState::Pending {
+                next: settings().schedule.phases[1].clone()
+            }
+        );
+        // Skipping a pending phase starts it.
+        app.skip();
+        assert_eq!(app.state().name(), "Short break");
+        assert_eq!(time_left(&app), Some(MINUTE * 5));
+    }
+
+    #[test]
+    fn runs_a_custom_schedule_and_starts_over() {
+        let (mut app, clock) = app(Settings {
+            schedule: "work 50, break:Stretch 10 green, work 90, break 30"
+                .parse()
+                .unwrap(),
+            ..settings()
+        });
+        app.start();
+        for (minutes, name, left) in [
+            (50, "Stretch", 10),
+            (10, "Work", 90),
+            (90, "Break", 30),
+            (30, "Work", 50),
+        ] {
+            wait(&mut app, &clock, minutes);
+            assert_eq!(app.state().name(), name);
+            assert_eq!(time_left(&app), Some(MINUTE * left));
+        }
+        assert_eq!(app.cycle(), Some(0));
+        assert_eq!(app.pomodoros(), 2);
+    }
+
+    #[test]
+    fn colours_follow_the_phase() {
+        let (mut app, clock) = app(Settings {
+            schedule: "work 50, break:Stretch 10 green".parse().unwrap(),
+            ..settings()
+        });
+        assert_eq!(app.get_color(), Color::Gray);
+        app.start();
+        wait(&mut app, &clock, 50);
+        assert_eq!(app.get_color(), Color::Green);
+    }
+
+    #[test]
+    fn stops_after_a_number_of_sessions() {
+        let (mut app, clock) = app(Settings {
+            sessions: Some(2),
+            ..settings()
+        });
+        app.start();
+        for minutes in [25, 5, 25] {
+            wait(&mut app, &clock, minutes);
+        }
+        assert_eq!(app.state(), &PomoState::Complete);
+        assert_eq!(
+            app.get_state_text(),
+            "Session complete: 2 pomodoros, 50 min focused - press 's' to start again"
+        );
+        wait(&mut app, &clock, 60);
+        assert_eq!(app.state(), &PomoState::Complete);
+    }
+
+    #[test]
+    fn stops_at_a_time_of_day() {
+        let clock = ManualClock::new();
+        let mut app = App::new(
+            Settings {
+                until: Some(clock.system_time() + MINUTE * 40),
+                ..settings()
+            },
+            clock.clone(),
+        );
+        app.start();
+        wait(&mut app, &clock, 25);
+        wait(&mut app, &clock, 5);
+        assert_eq!(app.state().name(), "Work");
+        wait(&mut app, &clock, 25);
+        assert_eq!(app.state(), &PomoState::Complete);
+        assert_eq!(app.pomodoros(), 2);
+    }
+
+    #[test]
+    fn flowtime_breaks_follow_the_work() {
+        let (mut app, clock) = app(Settings {
+            flowtime: true,
+            ..settings()
+        });
+        app.start();
+        wait(&mut app, &clock, 50);
+        assert_eq!(
+            app.state(),
+            &PomoState::Flow {
+                phase: Phase::new("Work", PhaseKind::Work, MINUTE * 25),
+                elapsed: MINUTE * 50,
+            }
+        );
+        assert_eq!(app.get_ratio(), 50. / 75.);
+        assert!(app.get_state_text().starts_with("Work: 50:00"));
+        app.confirm();
+        assert_eq!(app.state().name(), "Short break");
+        assert_eq!(time_left(&app), Some(MINUTE * 10));
+        let transition = &app.drain_transitions()[0];
+        assert_eq!(transition.planned, None);
+        assert_eq!(transition.actual, MINUTE * 50);
+    }
+
+    #[test]
+    fn flowtime_breaks_from_a_table() {
+        let (mut app, clock) = app(Settings {
+            flowtime: true,
+            flow_breaks: "25m=5m, 50m=8m, 15m".parse().unwrap(),
+            ..settings()
+        });
+        app.start();
+        wait(&mut app, &clock, 40);
+        app.skip();
+        assert_eq!(time_left(&app), Some(MINUTE * 8));
+        wait(&mut app, &clock, 8);
+        wait(&mut app, &clock, 120);
+        app.confirm();
+        assert_eq!(time_left(&app), Some(MINUTE * 15));
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Source of time for the timer.
+///
+/// `App` only ever asks its clock for the current time, so swapping the
+/// clock makes the state machine deterministic.
+pub trait Clock {
+    /// Monotonic time used to count down phases.
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time, only used for deadlines and timestamps.
+    fn system_time(&self) -> SystemTime;
+}
+
+/// Monotonic clock backed by `Instant`, unaffected by system clock changes.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MonotonicClock;
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock that only moves when `advance` is called.
+/// Clones share the same time, so a copy can be kept to drive an `App`.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    start: Instant,
+    start_system_time: SystemTime,
+    now: Rc<Cell<Instant>>,
+}
+
+impl ManualClock {
+    /// A clock starting at the current time.
+    pub fn new() -> Self {
+        Self::starting_at(SystemTime::now())
+    }
+
+    /// A clock whose wall-clock time starts at `system_time`.
+    pub fn starting_at(system_time: SystemTime) -> Self {
+        let start = Instant::now();
+        Self {
+            start,
+            start_system_time: system_time,
+            now: Rc::new(Cell::new(start)),
+        }
+    }
+
+    pub fn advance(&self, delta: Duration) {
+        self.now.set(self.now.get() + delta);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.start_system_time + (self.now.get() - self.start)
+    }
+}
@@ -0,0 +1,620 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    files::xdg_dir,
+    format_duration,
+    localtime::TimeOfDay,
+    parse_duration,
+    toml::{self, Entry, Value},
+    Action, Chord, KeyMap, Options, OptionsPatch,
+};
+
+/// Keys accepted at the top level of the config file.
+const OPTION_KEYS: &[&str] = &[
+    "work_time",
+    "short_wait_time",
+    "long_wait_time",
+    "cycles",
+    "dark_mode",
+    "adjust_step",
+    "overtime",
+    "confirm_transitions",
+    "schedule",
+    "sessions",
+    "until",
+    "flowtime",
+    "flow_breaks",
+    "confirm_actions",
+    "after_void",
+    "reflect",
+    "daily_goal",
+];
+
+/// Contents of a `config.toml` file.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Config {
+    pub options: OptionsPatch,
+    /// Named presets from `[profiles.NAME]` tables.
+    pub profiles: BTreeMap<String, OptionsPatch>,
+    /// The default keys with the `[keys]` table applied.
+    pub keys: KeyMap,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/pomotui/config.toml`, falling back to
+    /// `~/.config/pomotui/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("pomotui").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let src = fs::read_to_string(path).map_err(|error| ConfigError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        Self::parse(&src).map_err(|error| error.with_path(path))
+    }
+
+    pub fn parse(src: &str) -> Result<Self, ConfigError> {
+        let entries = toml::parse(src).map_err(|error| ConfigError::Parse {
+            path: None,
+            line: error.line,
+            message: error.message,
+        })?;
+        let mut config = Config::default();
+        let mut bound: Vec<(Chord, Action)> = Vec::new();
+        for entry in entries {
+            let error = |message: String| ConfigError::Parse {
+                path: None,
+                line: entry.line,
+                message,
+            };
+            match entry.table.as_slice() {
+                [] => set_option(&mut config.options, &entry).map_err(error)?,
+                [profiles, name] if profiles == "profiles" => {
+                    let profile = config.profiles.entry(name.clone()).or_default();
+                    set_option(profile, &entry).map_err(error)?
+                }
+                [keys] if keys == "keys" => {
+                    let action: Action = entry.key.parse().map_err(error)?;
+                    let chords = chords(&entry.value).map_err(error)?;
+                    for chord in &chords {
+                        if let Some((_, other)) = bound
+                            .iter()
+                            .find(|(other, _)| other.overlaps(chord))
+                            .filter(|(_, other)| *other != action)
+                        {
+                            return Err(error(format!(
+                                "'{chord}' is already bound to {}",
+                                other.name()
+                            )));
+                        }
+                        bound.push((chord.clone(), action));
+                    }
+                    config.keys.bind(action, chords);
+                }
+                table => return Err(error(format!("unknown table [{}]", table.join(".")))),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Layers the config file, the profile called `profile` and `overrides`
+    /// (environment and command line) over the defaults, in that order.
+    pub fn options(
+        &self,
+        profile: Option<&str>,
+        overrides: &OptionsPatch,
+    ) -> Result<Options, ConfigError> {
+        let mut options = Options::default();
+        self.options.apply(&mut options);
+        if let Some(name) = profile {
+            let profile = self
+                .profiles
+                .get(name)
+                .ok_or_else(|| ConfigError::UnknownProfile {
+                    name: name.to_string(),
+                    available: self.profiles.keys().cloned().collect(),
+                })?;
+            profile.apply(&mut options);
+        }
+        overrides.apply(&mut options);
+        options.keys = self.keys.clone();
+        Ok(options)
+    }
+
+    /// A config file with every option commented out at its default value.
+    pub fn template() -> String {
+        let defaults = Options::default();
+        format!(
+            r#"# pomotui configuration
+#
+# Options given here are overridden by POMOTUI_* environment variables and
+# command line flags. Durations are minutes (25) or strings such as "25m",
+# "90s", "1h15m" or "1:30".
+
+# Length of work phases
+# work_time = "{work_time}"
+
+# Length of short breaks
+# short_wait_time = "{short_wait_time}"
+
+# Length of the long break after the last cycle
+# long_wait_time = "{long_wait_time}"
+
+# Work phases before a long break
+# cycles = {cycles}
+
+# dark_mode = {dark_mode}
+
+# Time added or removed by '+' and '-'
+# adjust_step = "{adjust_step}"
+
+# Keep counting past the end of a phase until confirmed with Enter
+# overtime = {overtime}
+
+# Wait for Enter before starting each phase
+# confirm_transitions = {confirm_transitions}
+
+# Custom phases instead of the work/short/long cycle
+# schedule = "work 50, break 10, work 50, break 10, work 1h30m, break 30"
+
+# Stop after this many completed work phases
+# sessions = 8
+
+# Don't start new phases after this time of day, in UTC on systems other than
+# Unix
+# until = "17:30"
+
+# Count work phases up and end them with Enter
+# flowtime = {flowtime}
+
+# Flowtime breaks, "/5" for a fifth of the work time or a table like
+# "25m=5m, 50m=8m, 90m=10m, 15m"
+# flow_breaks = "{flow_breaks}"
+
+# Actions that ask for confirmation while a phase is running, one of start,
+# reset, pause, skip, extend, shorten, internal, external, void, confirm,
+# profiles, tasks, goal, stats, help and quit
+# confirm_actions = {confirm_actions}
+
+# Where to go after voiding a work phase, "menu" or "work" to start it over
+# after_void = "{after_void}"
+
+# Ask what you got done and how focused you were after each pomodoro
+# reflect = {reflect}
+
+# Pomodoros to aim for each day, shown next to the timer, 0 for no goal
+# daily_goal = 8
+
+# Named presets, picked with --profile NAME or with Tab in the menu. A
+# profile can set any of the options above.
+#
+# [profiles.deep-work]
+# work_time = "50m"
+# short_wait_time = "10m"
+# long_wait_time = "30m"
+# cycles = 3
+#
+# [profiles.classic]
+# work_time = "25m"
+# short_wait_time = "5m"
+# long_wait_time = "20m"
+# cycles = 4
+
+# Keys for each action, either one key or an array of them. Keys can have
+# modifiers ("ctrl+c", "alt+Enter") and be pressed in sequence ("g n").
+# Binding a key takes it away from the action it is bound to by default.
+#
+# [keys]
+{keys}"#,
+            work_time = format_duration(defaults.work_time),
+            short_wait_time = format_duration(defaults.short_wait_time),
+            long_wait_time = format_duration(defaults.long_wait_time),
+            cycles = defaults.cycles,
+            dark_mode = defaults.dark_mode,
+            adjust_step = format_duration(defaults.adjust_step),
+            overtime = defaults.overtime,
+            confirm_transitions = defaults.confirm_transitions,
+            flowtime = defaults.flowtime,
+            flow_breaks = defaults.flow_breaks,
+            confirm_actions = action_list(&defaults.confirm_actions),
+            after_void = defaults.after_void,
+            reflect = defaults.reflect,
+            keys = key_template(&defaults.keys),
+        )
+    }
+}
+
+/// Watches a config file for changes by polling its modification time and
+/// size.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+impl ConfigWatcher {
+    /// Watches `path` from its current contents on. A file that doesn't exist
+    /// yet is picked up once it is created.
+    pub fn new(path: PathBuf) -> Self {
+        let stamp = stamp(&path);
+        Self { path, stamp }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reloads the file if it changed since the last call, `None` while it is
+    /// unchanged or missing.
+    pub fn poll(&mut self) -> Option<Result<Config, ConfigError>> {
+        let stamp = stamp(&self.path)?;
+        if self.stamp == Some(stamp) {
+            return None;
+        }
+        self.stamp = Some(stamp);
+        Some(Config::load(&self.path))
+    }
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// The bindings of `keys` as commented out `[keys]` entries.
+fn key_template(keys: &KeyMap) -> String {
+    let mut out = String::new();
+    for action in Action::ALL {
+        let chords: Vec<String> = keys
+            .chords(action)
+            .map(|chord| toml::quote(&chord.to_string()))
+            .collect();
+        let value = match chords.as_slice() {
+            [chord] => chord.clone(),
+            chords => format!("[{}]", chords.join(", ")),
+        };
+        out.push_str(&format!("# {} = {}\n", action.name(), value));
+    }
+    out
+}
+
+fn set_option(options: &mut OptionsPatch, entry: &Entry) -> Result<(), String> {
+    let value = &entry.value;
+    match entry.key.as_str() {
+        "work_time" => options.work_time = Some(duration(value)?),
+        "short_wait_time" => options.short_wait_time = Some(duration(value)?),
+        "long_wait_time" => options.long_wait_time = Some(duration(value)?),
+        "cycles" => options.cycles = Some(count(value)?),
+        "dark_mode" => options.dark_mode = Some(boolean(value)?),
+        "adjust_step" => options.adjust_step = Some(duration(value)?),
+        "overtime" => options.overtime = Some(boolean(value)?),
+        "confirm_transitions" => options.confirm_transitions = Some(boolean(value)?),
+        "schedule" => options.schedule = Some(string(value)?.parse()?),
+        "sessions" => options.sessions = Some(count(value)?),
+        "until" => options.until = Some(string(value)?.parse::<TimeOfDay>()?),
+        "flowtime" => options.flowtime = Some(boolean(value)?),
+        "flow_breaks" => options.flow_breaks = Some(string(value)?.parse()?),
+        "confirm_actions" => options.confirm_actions = Some(actions(value)?),
+        "after_void" => options.after_void = Some(string(value)?.parse()?),
+        "reflect" => options.reflect = Some(boolean(value)?),
+        "daily_goal" => options.daily_goal = Some(count(value)?),
+        key => return Err(unknown_key(key, OPTION_KEYS)),
+    }
+    Ok(())
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+    match value {
+        Value::String(s) => Ok(s),
+        value => Err(format!("expected a string, found {}", value.type_name())),
+    }
+}
+
+fn boolean(value: &Value) -> Result<bool, String> {
+    match value {
+        Value::Boolean(b) => Ok(*b),
+        value => Err(format!(
+            "expected true or false, found {}",
+            value.type_name()
+        )),
+    }
+}
+
+fn count(value: &Value) -> Result<u32, String> {
+    match value {
+        Value::Integer(i) => u32::try_from(*i).map_err(|_| format!("{i} is out of range")),
+        value => Err(format!("expected a number, found {}", value.type_name())),
+    }
+}
+
+/// An array of action names.
+fn actions(value: &Value) -> Result<Vec<Action>, String> {
+    match value {
+        Value::Array(values) => values.iter().map(|value| string(value)?.parse()).collect(),
+        value => Err(format!(
+            "expected an array of actions, found {}",
+            value.type_name()
+        )),
+    }
+}
+
+fn action_list(actions: &[Action]) -> String {
+    let names: Vec<String> = actions
+        .iter()
+        .map(|action| toml::quote(action.name()))
+        .collect();
+    format!("[{}]", names.join(", "))
+}
+
+/// A chord or an array of them, an empty array unbinds the action.
+fn chords(value: &Value) -> Result<Vec<Chord>, String> {
+    match value {
+        Value::String(s) => Ok(vec![s.parse()?]),
+        Value::Array(values) => values.iter().map(|value| string(value)?.parse()).collect(),
+        value => Err(format!(
+            "expected a key or an array of keys, found {}",
+            value.type_name()
+        )),
+    }
+}
+
+/// Minutes as an integer or any duration [`parse_duration`] accepts.
+fn duration(value: &Value) -> Result<std::time::Duration, String> {
+    match value {
+        Value::Integer(i) => parse_duration(&i.to_string()),
+        Value::String(s) => parse_duration(s),
+        value => Err(format!("expected a duration, found {}", value.type_name())),
+    }
+}
+
+/// Error for an unrecognised key, suggesting the closest known one.
+pub(crate) fn unknown_key(key: &str, known: &[&str]) -> String {
+    unknown("key", key, known)
+}
+
+/// Error for an unrecognised `what` called `key`, suggesting the closest
+/// known one.
+pub(crate) fn unknown(what: &str, key: &str, known: &[&str]) -> String {
+    let closest = known
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .min();
+    match closest {
+        Some((distance, candidate)) if distance <= 3 => {
+            format!("unknown {what} '{key}', did you mean '{candidate}'?")
+        }
+        _ => format!("unknown {what} '{key}'"),
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Parse {
+        path: Option<PathBuf>,
+        line: usize,
+        message: String,
+    },
+    UnknownProfile {
+        name: String,
+        available: Vec<String>,
+    },
+}
+
+impl ConfigError {
+    fn with_path(self, path: &Path) -> Self {
+        match self {
+            ConfigError::Parse { line, message, .. } => ConfigError::Parse {
+                path: Some(path.to_path_buf()),
+                line,
+                message,
+            },
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ConfigError::Parse {
+                path: Some(path),
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            ConfigError::Parse {
+                path: None,
+                line,
+                message,
+            } => write!(f, "line {}: {}", line, message),
+            ConfigError::UnknownProfile { name, available } if available.is_empty() => {
+                write!(
+                    f,
+                    "unknown profile '{name}', the config file has no profiles"
+                )
+            }
+            ConfigError::UnknownProfile { name, available } => write!(
+                f,
+                "unknown profile '{name}', available profiles: {}",
+                available.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn error(src: &str) -> String {
+        Config::parse(src).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_options_and_keys() {
+        let config = Config::parse(
+            r#"
+# longer pomodoros
+work_time = 50
+short_wait_time = "7m30s" # a string duration
+cycles = 3
+dark_mode = true
+until = '17:30'
+confirm_actions = ["quit", "skip"]
+
+[keys]
+skip = ["n", "ctrl+s"]
+"#,
+        )
+        .unwrap();
+        let options = config.options(None, &OptionsPatch::default()).unwrap();
+        assert_eq!(options.work_time, MINUTE * 50);
+        assert_eq!(options.short_wait_time, MINUTE * 15 / 2);
+        assert_eq!(options.cycles, 3);
+        assert!(options.dark_mode);
+        assert_eq!(options.until, Some("17:30".parse().unwrap()));
+        assert_eq!(options.confirm_actions, vec![Action::Quit, Action::Skip]);
+        let skip: Vec<String> = options
+            .keys
+            .chords(Action::Skip)
+            .map(|chord| chord.to_string())
+            .collect();
+        assert_eq!(skip, ["n", "ctrl+s"]);
+        assert_eq!(options.long_wait_time, Options::default().long_wait_time);
+    }
+
+    #[test]
+    fn template_parses_to_the_defaults() {
+        let config = Config::parse(&Config::template()).unwrap();
+        assert_eq!(
+            config.options(None, &OptionsPatch::default()).unwrap(),
+            Options::default()
+        );
+    }
+
+    #[test]
+    fn suggests_the_closest_key() {
+        assert_eq!(
+            error("cycles = 2\nwork_tme = 30"),
+            "line 2: unknown key 'work_tme', did you mean 'work_time'?"
+        );
+        assert_eq!(error("colour = 1"), "line 1: unknown key 'colour'");
+        assert_eq!(
+            error("[colours]\ndark = 1"),
+            "line 2: unknown table [colours]"
+        );
+    }
+
+    #[test]
+    fn reports_bad_values_with_their_line() {
+        assert_eq!(
+            error("work_time = 25\n\ndark_mode = \"yes\""),
+            "line 3: expected true or false, found a string"
+        );
+        assert_eq!(error("cycles = -1"), "line 1: -1 is out of range");
+        assert_eq!(
+            error("cycles = 2\n[profiles.short]\nsessions = []"),
+            "line 3: expected a number, found an array"
+        );
+        assert!(
+            error("[keys]\nskip = \"n\"\nquit = \"n\"").starts_with("line 3: 'n' is already bound")
+        );
+    }
+
+    #[test]
+    fn overrides_win_over_the_file() {
+        let config = Config::parse("work_time = 50\ncycles = 3").unwrap();
+        let overrides = OptionsPatch {
+            work_time: Some(MINUTE * 30),
+            dark_mode: Some(true),
+            ..OptionsPatch::default()
+        };
+        let options = config.options(None, &overrides).unwrap();
+        assert_eq!(options.work_time, MINUTE * 30);
+        assert_eq!(options.cycles, 3);
+        assert!(options.dark_mode);
+    }
+
+    #[test]
+    fn profiles_override_the_base_options() {
+        let config = Config::parse(
+            r#"
+work_time = 30
+cycles = 3
+dark_mode = true
+
+[profiles.deep-work]
+work_time = "50m"
+cycles = 2
+
+[profiles.quick]
+short_wait_time = 2
+"#,
+        )
+        .unwrap();
+        let options = config
+            .options(Some("deep-work"), &OptionsPatch::default())
+            .unwrap();
+        assert_eq!(options.work_time, MINUTE * 50);
+        assert_eq!(options.cycles, 2);
+        assert!(options.dark_mode);
+        assert_eq!(options.short_wait_time, Options::default().short_wait_time);
+
+        let overrides = OptionsPatch {
+            cycles: Some(6),
+            ..OptionsPatch::default()
+        };
+        let options = config.options(Some("deep-work"), &overrides).unwrap();
+        assert_eq!(options.cycles, 6);
+    }
+
+    #[test]
+    fn unknown_profiles_are_an_error() {
+        let config = Config::parse("[profiles.deep-work]\ncycles = 2").unwrap();
+        assert_eq!(
+            config
+                .options(Some("deep"), &OptionsPatch::default())
+                .unwrap_err()
+                .to_string(),
+            "unknown profile 'deep', available profiles: deep-work"
+        );
+        assert_eq!(
+            Config::default()
+                .options(Some("deep"), &OptionsPatch::default())
+                .unwrap_err()
+                .to_string(),
+            "unknown profile 'deep', the config file has no profiles"
+        );
+    }
+}
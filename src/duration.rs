@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+/// Parses a duration such as `25m`, `90s`, `1h15m`, `1:30` (minutes and
+/// seconds) or `1:15:00` (hours, minutes and seconds). A bare number is taken
+/// as minutes. Zero and negative durations are rejected.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    if let Some(positive) = s.strip_prefix('-') {
+        return Err(match parse_duration(positive) {
+            Ok(_) => format!("duration '{s}' must be greater than zero"),
+            Err(e) => e,
+        });
+    }
+    let invalid =
+        || format!("invalid duration '{s}', expected e.g. '25', '25m', '90s', '1h15m' or '1:30'");
+    let seconds = if s.chars().all(|c| c.is_ascii_digit()) && !s.is_empty() {
+        s.parse::<u64>()
+            .ok()
+            .and_then(|minutes| minutes.checked_mul(60))
+            .ok_or_else(invalid)?
+    } else if s.contains(':') {
+        let parts = s
+            .split(':')
+            .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if parts.len() > 3 || parts[1..].iter().any(|part| *part >= 60) {
+            return Err(invalid());
+        }
+        parts
+            .iter()
+            .try_fold(0u64, |total, part| {
+                total.checked_mul(60)?.checked_add(*part)
+            })
+            .ok_or_else(invalid)?
+    } else {
+        let mut total = 0u64;
+        let mut number = String::new();
+        for c in s.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let unit = match c {
+                'h' => 60 * 60,
+                'm' => 60,
+                's' => 1,
+                _ => return Err(invalid()),
+            };
+            total = number
+                .parse::<u64>()
+                .ok()
+                .and_then(|number| number.checked_mul(unit))
+                .and_then(|seconds| total.checked_add(seconds))
+                .ok_or_else(invalid)?;
+            number.clear();
+        }
+        if !number.is_empty() {
+            return Err(invalid());
+        }
+        total
+    };
+    if seconds == 0 {
+        return Err(format!("duration '{s}' must be greater than zero"));
+    }
+    // Phases are timed in milliseconds as `i64`.
+    if seconds > i64::MAX as u64 / 1000 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Formats a duration the way [`parse_duration`] reads it, e.g. `1h15m`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    let mut text = String::new();
+    if hours > 0 {
+        text.push_str(&format!("{hours}h"));
+    }
+    if minutes > 0 {
+        text.push_str(&format!("{minutes}m"));
+    }
+    if seconds > 0 || text.is_empty() {
+        text.push_str(&format!("{seconds}s"));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: &str) -> Result<u64, String> {
+        parse_duration(s).map(|duration| duration.as_secs())
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(secs("25"), Ok(25 * 60));
+        assert_eq!(secs("25m"), Ok(25 * 60));
+        assert_eq!(secs("90s"), Ok(90));
+        assert_eq!(secs("1h15m"), Ok(75 * 60));
+        assert_eq!(secs("1:30"), Ok(90));
+        assert_eq!(secs("1:15:00"), Ok(75 * 60));
+        assert_eq!(secs(" 5m "), Ok(5 * 60));
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        for s in [
+            "", "0", "0m", "0:00", "-5", "-5m", "5x", "m", "5m3", "1:60", "1:2:3:4", "a:b",
+        ] {
+            assert!(parse_duration(s).is_err(), "accepted {s:?}");
+        }
+        assert_eq!(
+            parse_duration("-5m"),
+            Err("duration '-5m' must be greater than zero".into())
+        );
+        for s in [
+            "999999999999999999",
+            "999999999999999999h",
+            "18446744073709551615s",
+            "18446744073709551616s",
+            "99999999999999999:0:0",
+            "9223372036854776s",
+        ] {
+            assert!(
+                parse_duration(s)
+                    .unwrap_err()
+                    .starts_with("invalid duration"),
+                "accepted {s:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn format_reads_back() {
+        for secs in [1, 59, 60, 90, 3600, 4500, 5400 + 59, 86_400] {
+            let duration = Duration::from_secs(secs);
+            assert_eq!(parse_duration(&format_duration(duration)), Ok(duration));
+        }
+        assert_eq!(format_duration(Duration::from_secs(4530)), "1h15m30s");
+    }
+}
@@ -0,0 +1,270 @@
+use std::{fmt::Write, str::FromStr, time::SystemTime};
+
+use crate::{
+    config::unknown,
+    format_duration,
+    history::{kind_name, outcome_name},
+    localtime::{format_rfc3339, unix_seconds, Date},
+    InterruptionKind, Record,
+};
+
+/// File formats history can be exported to, see [`export`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// A header row and one row per record, durations in seconds.
+    Csv,
+    /// An array of records as written to the history file.
+    Json,
+    /// An iCalendar file with an event per record.
+    Ics,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Csv, Format::Json, Format::Ics];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Ics => "ics",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| unknown("format", s, &Format::ALL.map(Format::name)))
+    }
+}
+
+/// `records` written as `format`.
+pub fn export(records: &[Record], format: Format) -> String {
+    match format {
+        Format::Csv => csv(records),
+        Format::Json => json(records),
+        Format::Ics => ics(records),
+    }
+}
+
+fn csv(records: &[Record]) -> String {
+    let mut out = String::from(
+        "start,end,phase,kind,planned_secs,actual_secs,paused_secs,overtime_secs,outcome,task,\
+         internal_interruptions,external_interruptions,reason,note,focus\r\n",
+    );
+    for record in records {
+        let reflection = record.reflection.as_ref();
+        let fields = [
+            format_rfc3339(record.started),
+            format_rfc3339(record.ended),
+            csv_field(&record.name),
+            kind_name(record.kind).to_string(),
+            record
+                .planned
+                .map_or(String::new(), |planned| planned.as_secs().to_string()),
+            record.actual.as_secs().to_string(),
+            record.paused.as_secs().to_string(),
+            record.overtime.as_secs().to_string(),
+            outcome_name(record.outcome).to_string(),
+            record.task.as_deref().map_or(String::new(), csv_field),
+            record
+                .interruption_count(InterruptionKind::Internal)
+                .to_string(),
+            record
+                .interruption_count(InterruptionKind::External)
+                .to_string(),
+            record.reason.as_deref().map_or(String::new(), csv_field),
+            reflection
+                .and_then(|reflection| reflection.note.as_deref())
+                .map_or(String::new(), csv_field),
+            reflection
+                .and_then(|reflection| reflection.focus)
+                .map_or(String::new(), |focus| focus.to_string()),
+        ];
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Quotes `s` if it contains a separator, quote or line break.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// One record per line so the output stays readable and diffable.
+fn json(records: &[Record]) -> String {
+    if records.is_empty() {
+        return "[]\n".into();
+    }
+    let lines: Vec<String> = records
+        .iter()
+        .map(|record| record.json().to_string())
+        .collect();
+    format!("[\n{}\n]\n", lines.join(",\n"))
+}
+
+fn ics(records: &[Record]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".into(),
+        "PRODID:-//pomotui//pomotui//EN".into(),
+    ];
+    for record in records {
+        let mut description = outcome_name(record.outcome).to_string();
+        if let Some(reason) = &record.reason {
+            let _ = write!(description, " ({reason})");
+        }
+        let _ = write!(description, ", ran {}", format_duration(record.actual));
+        if let Some(planned) = record.planned {
+            let _ = write!(description, " of {} planned", format_duration(planned));
+        }
+        if !record.paused.is_zero() {
+            let _ = write!(description, ", paused {}", format_duration(record.paused));
+        }
+        if !record.interruptions.is_empty() {
+            let _ = write!(
+                description,
+                ", interrupted {} times",
+                record.interruptions.len()
+            );
+        }
+        if let Some(reflection) = &record.reflection {
+            if let Some(focus) = reflection.focus {
+                let _ = write!(description, ", focus {focus}/5");
+            }
+            if let Some(note) = &reflection.note {
+                let _ = write!(description, "\n{note}");
+            }
+        }
+        let summary = match &record.task {
+            Some(task) => format!("{} - {task}", record.name),
+            None => record.name.clone(),
+        };
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!(
+                "UID:{}-{}@pomotui",
+                unix_seconds(record.started),
+                kind_name(record.kind)
+            ),
+            format!("DTSTAMP:{}", ics_time(record.ended)),
+            format!("DTSTART:{}", ics_time(record.started)),
+            format!("DTEND:{}", ics_time(record.ended)),
+            format!("SUMMARY:{}", ics_text(&summary)),
+            format!("DESCRIPTION:{}", ics_text(&description)),
+            format!("CATEGORIES:{}", kind_name(record.kind)),
+            "END:VEVENT".into(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".into());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// `time` in UTC, e.g. `20240501T073000Z`.
+fn ics_time(time: SystemTime) -> String {
+    let secs = unix_seconds(time);
+    let date = Date::from_days(secs.div_euclid(86_400));
+    let secs = secs.rem_euclid(86_400);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        date.year,
+        date.month,
+        date.day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+fn ics_text(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Ends `line` with CRLF, breaking it into lines of at most 75 bytes
+/// continued with a leading space.
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{json::Json, localtime::from_unix_seconds};
+    use std::time::Duration;
+
+    fn record(name: &str) -> Record {
+        Record {
+            ended: from_unix_seconds(1_714_550_130),
+            name: name.into(),
+            paused: Duration::from_secs(30),
+            ..Record::sample(from_unix_seconds(1_714_548_600), 1500)
+        }
+    }
+
+    #[test]
+    fn writes_csv_and_ics() {
+        let records = [record("Deep, \"focused\" work")];
+        let csv = export(&records, Format::Csv);
+        let row = csv.lines().nth(1).unwrap();
+        assert!(
+            row.contains(",\"Deep, \"\"focused\"\" work\",work,1500,1500,30,0,completed,,0,0,,,")
+        );
+
+        let ics = export(&records, Format::Ics);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nDTSTART:20240501T073000Z\r\n"));
+        assert!(ics.contains("\r\nDTEND:20240501T075530Z\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Deep\\, \"focused\" work\r\n"));
+
+        let ics = export(&[record(&"x".repeat(100))], Format::Ics);
+        assert!(ics.contains(&format!(
+            "SUMMARY:{}\r\n {}\r\n",
+            "x".repeat(67),
+            "x".repeat(33)
+        )));
+    }
+
+    #[test]
+    fn json_reads_back() {
+        let json = export(&[record("Work"), record("Work")], Format::Json);
+        let records = crate::json::parse(&json).unwrap();
+        let Json::Array(records) = records else {
+            panic!("not an array: {json}");
+        };
+        assert_eq!(records.len(), 2);
+        let line = records[0].to_string();
+        assert_eq!(Record::from_json(&line), Ok(Some(record("Work"))));
+    }
+}
@@ -0,0 +1,26 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// `$variable`, `~/<home>` or `%APPDATA%`, e.g. `xdg_dir("XDG_DATA_HOME",
+/// ".local/share")`.
+pub(crate) fn xdg_dir(variable: &str, home: &str) -> Option<PathBuf> {
+    env::var_os(variable)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|dir| PathBuf::from(dir).join(home)))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+}
+
+/// Writes `contents` to `path`, replacing the previous file in a single step
+/// so a crash never leaves half a file behind.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    fs::write(&partial, contents)?;
+    fs::rename(&partial, path)
+}
@@ -0,0 +1,77 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::{format_duration, parse_duration};
+
+/// How long the break after a flowtime work phase is.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FlowBreaks {
+    /// The work time divided by this.
+    Ratio(u32),
+    /// `(work up to, break)` pairs checked in order. Work longer than every
+    /// entry gets the last break.
+    Table(Vec<(Option<Duration>, Duration)>),
+}
+
+impl FlowBreaks {
+    /// Break after `work` of flowtime.
+    pub fn break_for(&self, work: Duration) -> Duration {
+        match self {
+            FlowBreaks::Ratio(ratio) => work / (*ratio).max(1),
+            FlowBreaks::Table(rules) => rules
+                .iter()
+                .find(|(up_to, _)| up_to.is_none_or(|up_to| work <= up_to))
+                .or(rules.last())
+                .map_or(Duration::ZERO, |(_, brk)| *brk),
+        }
+    }
+}
+
+impl Default for FlowBreaks {
+    fn default() -> Self {
+        FlowBreaks::Ratio(5)
+    }
+}
+
+/// Parses either `/N` for a break of work/N, or a comma separated table of
+/// `WORK=BREAK` durations with an optional bare `BREAK` for anything longer,
+/// e.g. `25=5, 50=8, 1h30m=10, 15`.
+impl FromStr for FlowBreaks {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(ratio) = s.trim().strip_prefix('/') {
+            return match ratio.trim().parse() {
+                Ok(ratio) if ratio > 0 => Ok(FlowBreaks::Ratio(ratio)),
+                _ => Err(format!("invalid break ratio '{ratio}'")),
+            };
+        }
+        let rules = s
+            .split(',')
+            .map(|rule| match rule.split_once('=') {
+                Some((up_to, brk)) => Ok((Some(parse_duration(up_to)?), parse_duration(brk)?)),
+                None => Ok((None, parse_duration(rule)?)),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(FlowBreaks::Table(rules))
+    }
+}
+
+impl fmt::Display for FlowBreaks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlowBreaks::Ratio(ratio) => write!(f, "/{ratio}"),
+            FlowBreaks::Table(rules) => {
+                for (i, (up_to, brk)) in rules.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if let Some(up_to) = up_to {
+                        write!(f, "{}=", format_duration(*up_to))?;
+                    }
+                    write!(f, "{}", format_duration(*brk))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    files::xdg_dir,
+    json::{self, Json},
+    localtime::{format_rfc3339, parse_rfc3339},
+    Interruption, InterruptionKind, PhaseKind, PhaseOutcome, Transition,
+};
+
+/// Version of the record format, written as `"v"` on every line. Fields may
+/// be added without changing it, readers skip records from a newer version.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// An ended phase as stored in the history file.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Record {
+    pub started: SystemTime,
+    pub ended: SystemTime,
+    /// Name of the phase, e.g. "Work" or "Long break".
+    pub name: String,
+    pub kind: PhaseKind,
+    /// Scheduled length, `None` for flowtime work.
+    pub planned: Option<Duration>,
+    /// Time the phase ran for, not counting pauses.
+    pub actual: Duration,
+    pub paused: Duration,
+    pub overtime: Duration,
+    pub outcome: PhaseOutcome,
+    /// Task a work phase was attributed to.
+    pub task: Option<String>,
+    pub interruptions: Vec<Interruption>,
+    /// Why a voided phase was voided.
+    pub reason: Option<String>,
+    /// What got done in a completed work phase, if it was asked for.
+    pub reflection: Option<Reflection>,
+}
+
+/// Answers to "what did you get done?" after a work phase.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Reflection {
+    pub note: Option<String>,
+    /// How focused the work was, from 1 to 5.
+    pub focus: Option<u8>,
+}
+
+impl Record {
+    /// The record for a transition, `None` if no phase was running.
+    pub fn from_transition(transition: &Transition) -> Option<Self> {
+        let phase = transition.from.phase()?;
+        Some(Self {
+            started: transition.started,
+            ended: transition.ended,
+            name: phase.name.clone(),
+            kind: phase.kind,
+            planned: transition.planned,
+            actual: transition.actual,
+            paused: transition.paused,
+            overtime: transition.overtime,
+            outcome: transition.outcome,
+            task: transition.task.clone(),
+            interruptions: transition.interruptions.clone(),
+            reason: transition.reason.clone(),
+            reflection: None,
+        })
+    }
+
+    /// Whether this is a completed work phase, the kind asked about with
+    /// `settings.reflect`.
+    pub fn can_reflect(&self) -> bool {
+        self.kind == PhaseKind::Work && self.outcome == PhaseOutcome::Completed
+    }
+
+    /// Number of interruptions of `kind`.
+    pub fn interruption_count(&self, kind: InterruptionKind) -> u32 {
+        self.interruptions
+            .iter()
+            .filter(|interruption| interruption.kind == kind)
+            .count() as u32
+    }
+
+    /// The record as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        self.json().to_string()
+    }
+
+    pub(crate) fn json(&self) -> Json {
+        let secs = |duration: Duration| Json::from(duration.as_secs());
+        let mut members = vec![
+            ("v".into(), SCHEMA_VERSION.into()),
+            ("start".into(), format_rfc3339(self.started).into()),
+            ("end".into(), format_rfc3339(self.ended).into()),
+            ("phase".into(), self.name.as_str().into()),
+            ("kind".into(), kind_name(self.kind).into()),
+            (
+                "planned_secs".into(),
+                self.planned.map(|d| d.as_secs()).into(),
+            ),
+            ("actual_secs".into(), secs(self.actual)),
+            ("paused_secs".into(), secs(self.paused)),
+            ("overtime_secs".into(), secs(self.overtime)),
+            ("outcome".into(), outcome_name(self.outcome).into()),
+        ];
+        if let Some(task) = &self.task {
+            members.push(("task".into(), task.as_str().into()));
+        }
+        if let Some(reason) = &self.reason {
+            members.push(("reason".into(), reason.as_str().into()));
+        }
+        if let Some(reflection) = &self.reflection {
+            members.push(("reflection".into(), reflection.json()));
+        }
+        if !self.interruptions.is_empty() {
+            members.push((
+                "interruptions".into(),
+                Json::Array(
+                    self.interruptions
+                        .iter()
+                        .map(interruption_to_json)
+                        .collect(),
+                ),
+            ));
+        }
+        Json::Object(members)
+    }
+
+    /// Reads a line written by [`Record::to_json`]. `Ok(None)` for a record
+    /// from a newer schema version.
+    pub fn from_json(line: &str) -> Result<Option<Self>, String> {
+        let json = json::parse(line)?;
+        if !supported_version(&json)? {
+            return Ok(None);
+        }
+        Self::from_json_value(&json).map(Some)
+    }
+
+    fn from_json_value(json: &Json) -> Result<Self, String> {
+        let field = |key: &str| json.get(key).ok_or_else(|| format!("missing '{key}'"));
+        let string = |key: &str| {
+            field(key)?
+                .as_str()
+                .ok_or_else(|| format!("'{key}' is not a string"))
+        };
+        let secs = |key: &str| {
+            field(key)?
+                .as_u64()
+                .map(Duration::from_secs)
+                .ok_or_else(|| format!("'{key}' is not a number of seconds"))
+        };
+        Ok(Self {
+            started: parse_rfc3339(string("start")?)?,
+            ended: parse_rfc3339(string("end")?)?,
+            name: string("phase")?.to_string(),
+            kind: parse_kind(string("kind")?)?,
+            planned: match field("planned_secs")? {
+                Json::Null => None,
+                _ => Some(secs("planned_secs")?),
+            },
+            actual: secs("actual_secs")?,
+            paused: secs("paused_secs")?,
+            overtime: secs("overtime_secs")?,
+            outcome: match string("outcome")? {
+                "completed" => PhaseOutcome::Completed,
+                "skipped" => PhaseOutcome::Skipped,
+                "abandoned" => PhaseOutcome::Abandoned,
+                "voided" => PhaseOutcome::Voided,
+                outcome => return Err(format!("unknown outcome '{outcome}'")),
+            },
+            task: json.get("task").and_then(Json::as_str).map(str::to_string),
+            interruptions: interruptions_from_json(json.get("interruptions"))?,
+            reason: json
+                .get("reason")
+                .and_then(Json::as_str)
+                .map(str::to_string),
+            reflection: json.get("reflection").map(Reflection::from_json),
+        })
+    }
+}
+
+impl Reflection {
+    fn json(&self) -> Json {
+        let mut members = Vec::new();
+        if let Some(note) = &self.note {
+            members.push(("note".into(), note.as_str().into()));
+        }
+        if let Some(focus) = self.focus {
+            members.push(("focus".into(), u64::from(focus).into()));
+        }
+        Json::Object(members)
+    }
+
+    fn from_json(json: &Json) -> Self {
+        Self {
+            note: json.get("note").and_then(Json::as_str).map(str::to_string),
+            focus: json
+                .get("focus")
+                .and_then(Json::as_u64)
+                .map(|focus| focus.min(5) as u8),
+        }
+    }
+}
+
+/// Whether `line` is from a schema version this build can read.
+fn supported_version(line: &Json) -> Result<bool, String> {
+    let version = line
+        .get("v")
+        .and_then(Json::as_u64)
+        .ok_or("missing schema version")?;
+    Ok(version <= SCHEMA_VERSION)
+}
+
+pub(crate) fn interruption_to_json(interruption: &Interruption) -> Json {
+    let kind = match interruption.kind {
+        InterruptionKind::Internal => "internal",
+        InterruptionKind::External => "external",
+    };
+    let mut members = vec![
+        ("kind".into(), kind.into()),
+        ("at".into(), format_rfc3339(interruption.at).into()),
+    ];
+    if let Some(note) = &interruption.note {
+        members.push(("note".into(), note.as_str().into()));
+    }
+    Json::Object(members)
+}
+
+/// Reads an array of interruptions, which may be missing.
+pub(crate) fn interruptions_from_json(json: Option<&Json>) -> Result<Vec<Interruption>, String> {
+    let items = match json {
+        None | Some(Json::Null) => return Ok(Vec::new()),
+        Some(Json::Array(items)) => items,
+        Some(_) => return Err("'interruptions' is not an array".into()),
+    };
+    items
+        .iter()
+        .map(|item| {
+            let string = |key: &str| {
+                item.get(key)
+                    .and_then(Json::as_str)
+                    .ok_or_else(|| format!("invalid interruption '{key}'"))
+            };
+            Ok(Interruption {
+                kind: match string("kind")? {
+                    "internal" => InterruptionKind::Internal,
+                    "external" => InterruptionKind::External,
+                    kind => return Err(format!("unknown interruption kind '{kind}'")),
+                },
+                at: parse_rfc3339(string("at")?)?,
+                note: item.get("note").and_then(Json::as_str).map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn kind_name(kind: PhaseKind) -> &'static str {
+    match kind {
+        PhaseKind::Work => "work",
+        PhaseKind::Break => "break",
+    }
+}
+
+pub(crate) fn parse_kind(kind: &str) -> Result<PhaseKind, String> {
+    match kind {
+        "work" => Ok(PhaseKind::Work),
+        "break" => Ok(PhaseKind::Break),
+        kind => Err(format!("unknown kind '{kind}'")),
+    }
+}
+
+pub(crate) fn outcome_name(outcome: PhaseOutcome) -> &'static str {
+    match outcome {
+        PhaseOutcome::Completed => "completed",
+        PhaseOutcome::Skipped => "skipped",
+        PhaseOutcome::Abandoned => "abandoned",
+        PhaseOutcome::Voided => "voided",
+    }
+}
+
+/// The JSON Lines file ended phases are appended to.
+#[derive(Clone, Debug)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// `$XDG_DATA_HOME/pomotui/history.jsonl`, falling back to
+    /// `~/.local/share/pomotui/history.jsonl`.
+    pub fn default_path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join("pomotui").join("history.jsonl"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends `records`, creating the file and its directory if needed.
+    pub fn append(&self, records: &[Record]) -> io::Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut lines = String::new();
+        for record in records {
+            lines.push_str(&record.to_json());
+            lines.push('\n');
+        }
+        self.append_lines(&lines)
+    }
+
+    /// Appends `reflection` on the work phase that started at `started`. It
+    /// goes on a line of its own, so the phase can be written as soon as it
+    /// ends, and [`History::load`] attaches it to the phase's record.
+    pub fn append_reflection(
+        &self,
+        started: SystemTime,
+        reflection: &Reflection,
+    ) -> io::Result<()> {
+        self.append_lines(&(reflection_line(started, reflection) + "\n"))
+    }
+
+    fn append_lines(&self, lines: &str) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(lines.as_bytes())
+    }
+
+    /// Reads every record, oldest first. A missing file is an empty history,
+    /// and lines that can't be read (or come from a newer version) are
+    /// skipped.
+    pub fn load(&self) -> io::Result<Vec<Record>> {
+        let src = match fs::read_to_string(&self.path) {
+            Ok(src) => src,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        };
+        Ok(read_records(&src))
+    }
+}
+
+fn reflection_line(started: SystemTime, reflection: &Reflection) -> String {
+    Json::Object(vec![
+        ("v".into(), SCHEMA_VERSION.into()),
+        ("reflects".into(), format_rfc3339(started).into()),
+        ("reflection".into(), reflection.json()),
+    ])
+    .to_string()
+}
+
+/// The records in the lines of a history file, with the reflections written
+/// after them by [`History::append_reflection`] attached.
+fn read_records(src: &str) -> Vec<Record> {
+    let mut records: Vec<Record> = Vec::new();
+    for line in src.lines().filter(|line| !line.trim().is_empty()) {
+        let Ok(json) = json::parse(line) else {
+            continue;
+        };
+        if !supported_version(&json).unwrap_or(false) {
+            continue;
+        }
+        if let Some(reflects) = json.get("reflects").and_then(Json::as_str) {
+            let (Ok(started), Some(reflection)) = (parse_rfc3339(reflects), json.get("reflection"))
+            else {
+                continue;
+            };
+            if let Some(record) = records.iter_mut().rev().find(|r| r.started == started) {
+                record.reflection = Some(Reflection::from_json(reflection));
+            }
+        } else if let Ok(record) = Record::from_json_value(&json) {
+            records.push(record);
+        }
+    }
+    records
+}
+
+/// `$XDG_DATA_HOME`, `~/.local/share` or `%APPDATA%`.
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+#[cfg(test)]
+impl Record {
+    /// A work phase planned as 25 minutes that started at `started` and was
+    /// completed after `actual` seconds, for tests to adjust.
+    pub(crate) fn sample(started: SystemTime, actual: u64) -> Self {
+        Record {
+            started,
+            ended: started + Duration::from_secs(actual),
+            name: "Work".into(),
+            kind: PhaseKind::Work,
+            planned: Some(Duration::from_secs(1500)),
+            actual: Duration::from_secs(actual),
+            paused: Duration::ZERO,
+            overtime: Duration::ZERO,
+            outcome: PhaseOutcome::Completed,
+            task: None,
+            interruptions: Vec::new(),
+            reason: None,
+            reflection: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::localtime::from_unix_seconds;
+
+    fn record() -> Record {
+        Record {
+            ended: from_unix_seconds(1_700_001_560),
+            name: "Work \"deep\"".into(),
+            paused: Duration::from_secs(30),
+            overtime: Duration::from_secs(30),
+            task: Some("Write report".into()),
+            interruptions: vec![
+                Interruption {
+                    kind: InterruptionKind::External,
+                    at: from_unix_seconds(1_700_000_600),
+                    note: Some("phone".into()),
+                },
+                Interruption {
+                    kind: InterruptionKind::Internal,
+                    at: from_unix_seconds(1_700_000_900),
+                    note: None,
+                },
+            ],
+            reflection: Some(Reflection {
+                note: Some("Outlined the report".into()),
+                focus: Some(4),
+            }),
+            ..Record::sample(from_unix_seconds(1_700_000_000), 1530)
+        }
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let record = record();
+        assert_eq!(Record::from_json(&record.to_json()), Ok(Some(record)));
+        let flow = Record {
+            planned: None,
+            outcome: PhaseOutcome::Abandoned,
+            task: None,
+            interruptions: Vec::new(),
+            reflection: None,
+            ..self::record()
+        };
+        assert_eq!(Record::from_json(&flow.to_json()), Ok(Some(flow)));
+        let voided = Record {
+            outcome: PhaseOutcome::Voided,
+            reason: Some("fire alarm".into()),
+            ..self::record()
+        };
+        assert_eq!(Record::from_json(&voided.to_json()), Ok(Some(voided)));
+    }
+
+    #[test]
+    fn attaches_reflections_written_later() {
+        let work = Record {
+            reflection: None,
+            ..record()
+        };
+        let reflection = Reflection {
+            note: Some("Outlined the report".into()),
+            focus: Some(4),
+        };
+        let later = Record::sample(work.ended, 300);
+        let lines = [
+            work.to_json(),
+            later.to_json(),
+            reflection_line(work.started, &reflection),
+            // No such phase.
+            reflection_line(later.ended, &reflection),
+        ];
+        assert_eq!(
+            read_records(&lines.join("\n")),
+            [
+                Record {
+                    reflection: Some(reflection),
+                    ..work
+                },
+                later
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_corrupt_lines() {
+        let first = Record::sample(from_unix_seconds(1_700_000_000), 1500);
+        let second = Record::sample(first.ended, 1500);
+        let line = second.to_json();
+        let lines = [
+            "not json".to_string(),
+            first.to_json(),
+            r#"{"v":1,"started":"yesterday"}"#.to_string(),
+            line[..line.len() / 2].to_string(),
+            String::new(),
+            line,
+        ];
+        assert_eq!(read_records(&lines.join("\n")), [first, second]);
+    }
+
+    #[test]
+    fn skips_newer_versions() {
+        let line = record().to_json().replacen("\"v\":1", "\"v\":2", 1);
+        assert_eq!(Record::from_json(&line), Ok(None));
+    }
+}
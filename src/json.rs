@@ -0,0 +1,408 @@
+//! Just enough JSON for the history file: one object per line, written and
+//! read back without pulling in a serializer.
+
+use std::fmt::{self, Write};
+
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members in the order they were written.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Member `key` of an object.
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// A whole number.
+    pub(crate) fn as_i64(&self) -> Option<i64> {
+        self.as_f64().filter(|n| n.fract() == 0.0).map(|n| n as i64)
+    }
+
+    /// A non-negative whole number.
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        self.as_f64()
+            .filter(|n| *n >= 0.0 && n.fract() == 0.0)
+            .map(|n| n as u64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(n: u64) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(n: i64) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+/// Writes compact JSON on a single line.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Number(n) if n.is_finite() => write!(f, "{n}"),
+            Json::Number(_) => f.write_str("null"),
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(members) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+pub(crate) fn parse(src: &str) -> Result<Json, String> {
+    let mut parser = Parser { src, pos: 0 };
+    let value = parser.value()?;
+    parser.skip_space();
+    if parser.pos < src.len() {
+        return Err(format!("unexpected text at column {}", parser.pos + 1));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_space();
+        match self.bump() {
+            Some(found) if found == c => Ok(()),
+            Some(found) => Err(format!("expected '{c}', found '{found}'")),
+            None => Err(format!("expected '{c}', found the end")),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        if self.src[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(format!("unexpected text at column {}", self.pos + 1))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_space();
+        match self.peek() {
+            Some('n') => self.literal("null", Json::Null),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.bump();
+                let mut items = Vec::new();
+                self.skip_space();
+                if self.peek() == Some(']') {
+                    self.bump();
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_space();
+                    match self.bump() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Json::Array(items)),
+                        _ => return Err("expected ',' or ']'".into()),
+                    }
+                }
+            }
+            Some('{') => {
+                self.bump();
+                let mut members = Vec::new();
+                self.skip_space();
+                if self.peek() == Some('}') {
+                    self.bump();
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip_space();
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    members.push((key, self.value()?));
+                    self.skip_space();
+                    match self.bump() {
+                        Some(',') => {}
+                        Some('}') => return Ok(Json::Object(members)),
+                        _ => return Err("expected ',' or '}'".into()),
+                    }
+                }
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    self.pos += 1;
+                }
+                let number = &self.src[start..self.pos];
+                number
+                    .parse()
+                    .map(Json::Number)
+                    .map_err(|_| format!("invalid number '{number}'"))
+            }
+            Some(c) => Err(format!("unexpected '{c}'")),
+            None => Err("unexpected end".into()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('/') => s.push('/'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('u') => s.push(self.unicode_escape()?),
+                    _ => return Err("invalid escape".into()),
+                },
+                Some(c) => s.push(c),
+                None => return Err("unterminated string".into()),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let unit = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&unit) {
+            // A surrogate pair.
+            if !self.src[self.pos..].starts_with("\\u") {
+                return Err("unpaired surrogate".into());
+            }
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err("unpaired surrogate".into());
+            }
+            0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            unit
+        };
+        char::from_u32(code).ok_or_else(|| "invalid \\u escape".into())
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .src
+            .get(self.pos..self.pos + 4)
+            .ok_or("invalid \\u escape")?;
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err("invalid \\u escape".into());
+        }
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(src: &str) -> Result<String, String> {
+        parse(src).map(|json| json.as_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn parses_values() {
+        let json = parse(r#" {"a": [1, -2.5, 3e2, true, null], "b": {}, "c": []} "#).unwrap();
+        assert_eq!(
+            json,
+            Json::Object(vec![
+                (
+                    "a".into(),
+                    Json::Array(vec![
+                        Json::Number(1.0),
+                        Json::Number(-2.5),
+                        Json::Number(300.0),
+                        Json::Bool(true),
+                        Json::Null,
+                    ])
+                ),
+                ("b".into(), Json::Object(vec![])),
+                ("c".into(), Json::Array(vec![])),
+            ])
+        );
+        assert_eq!(parse("1500").unwrap().as_u64(), Some(1500));
+        assert_eq!(parse("-3").unwrap().as_u64(), None);
+        assert_eq!(parse("-3").unwrap().as_i64(), Some(-3));
+        assert_eq!(parse("1.5").unwrap().as_i64(), None);
+    }
+
+    #[test]
+    fn parses_escapes() {
+        assert_eq!(
+            string(r#""a\"b\\c\/d\n\r\t\b\f""#).unwrap(),
+            "a\"b\\c/d\n\r\t\u{8}\u{c}"
+        );
+        assert_eq!(string(r#""\u00e9\u0041""#).unwrap(), "éA");
+        assert_eq!(string(r#""\ud83c\udf45 tomato""#).unwrap(), "🍅 tomato");
+        assert_eq!(string(r#""\ud83c""#).unwrap_err(), "unpaired surrogate");
+        assert_eq!(
+            string(r#""\ud83c\u0041""#).unwrap_err(),
+            "unpaired surrogate"
+        );
+        assert_eq!(string(r#""\udf45""#).unwrap_err(), "invalid \\u escape");
+        assert_eq!(string(r#""\u+abc""#).unwrap_err(), "invalid \\u escape");
+        assert_eq!(string(r#""\u12""#).unwrap_err(), "invalid \\u escape");
+        assert_eq!(string(r#""\q""#).unwrap_err(), "invalid escape");
+    }
+
+    #[test]
+    fn rejects_malformed_and_truncated_input() {
+        for src in [
+            "",
+            "{",
+            r#"{"started":"2024-05-01T09:30:00+02:00","#,
+            r#"{"name":"Wo"#,
+            r#"{"a":1"#,
+            "[1, 2",
+            r#"{"a" 1}"#,
+            "{a:1}",
+            "[1 2]",
+            "nul",
+            "1.2.3",
+            "-",
+            "{} {}",
+        ] {
+            assert!(parse(src).is_err(), "{src:?} parsed");
+        }
+        assert_eq!(parse("[1] x").unwrap_err(), "unexpected text at column 5");
+        assert_eq!(parse("--1").unwrap_err(), "invalid number '--1'");
+    }
+
+    #[test]
+    fn display_reads_back() {
+        let json = Json::Object(vec![
+            ("name".into(), "Work \"deep\"\n\t\\ \u{1} 🍅".into()),
+            ("actual".into(), 1530u64.into()),
+            ("offset".into(), (-7200i64).into()),
+            ("task".into(), None::<&str>.into()),
+            ("done".into(), true.into()),
+            ("list".into(), Json::Array(vec![Json::Number(0.5)])),
+        ]);
+        let line = json.to_string();
+        assert!(!line.contains('\n'));
+        assert_eq!(parse(&line), Ok(json));
+        assert_eq!(Json::Number(f64::NAN).to_string(), "null");
+    }
+}
@@ -0,0 +1,435 @@
+use std::{fmt, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{config::unknown, InterruptionKind};
+
+/// Something the user can do from the keyboard.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Action {
+    /// Start from the menu or after a finished session.
+    Start,
+    /// Start over from the first phase at any time.
+    Reset,
+    Pause,
+    Skip,
+    Extend,
+    Shorten,
+    /// Log an interruption of the running work phase.
+    Interrupt(InterruptionKind),
+    /// Call off the running work phase.
+    Void,
+    /// End an overtime or flowtime phase, or start a pending one.
+    Confirm,
+    Profiles,
+    /// Open the task list.
+    Tasks,
+    /// Change the number of pomodoros to aim for today.
+    Goal,
+    Stats,
+    Help,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 16] = [
+        Action::Start,
+        Action::Reset,
+        Action::Pause,
+        Action::Skip,
+        Action::Extend,
+        Action::Shorten,
+        Action::Interrupt(InterruptionKind::Internal),
+        Action::Interrupt(InterruptionKind::External),
+        Action::Void,
+        Action::Confirm,
+        Action::Profiles,
+        Action::Tasks,
+        Action::Goal,
+        Action::Stats,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// Name of the action in the `[keys]` table of the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Start => "start",
+            Action::Reset => "reset",
+            Action::Pause => "pause",
+            Action::Skip => "skip",
+            Action::Extend => "extend",
+            Action::Shorten => "shorten",
+            Action::Interrupt(InterruptionKind::Internal) => "internal",
+            Action::Interrupt(InterruptionKind::External) => "external",
+            Action::Void => "void",
+            Action::Confirm => "confirm",
+            Action::Profiles => "profiles",
+            Action::Tasks => "tasks",
+            Action::Goal => "goal",
+            Action::Stats => "stats",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    /// What the action does, to follow "press KEY to".
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Start => "start",
+            Action::Reset => "restart",
+            Action::Pause => "pause",
+            Action::Skip => "skip",
+            Action::Extend => "add time",
+            Action::Shorten => "remove time",
+            Action::Interrupt(InterruptionKind::Internal) => "log an internal interruption",
+            Action::Interrupt(InterruptionKind::External) => "log an external interruption",
+            Action::Void => "void the pomodoro",
+            Action::Confirm => "confirm",
+            Action::Profiles => "pick a profile",
+            Action::Tasks => "pick a task",
+            Action::Goal => "set the daily goal",
+            Action::Stats => "show statistics",
+            Action::Help => "show help",
+            Action::Quit => "quit",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name() == s)
+            .ok_or_else(|| unknown("action", s, &Action::ALL.map(Action::name)))
+    }
+}
+
+/// A key together with its modifiers, written like `s`, `Enter` or
+/// `ctrl+c`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of the character, and of BackTab.
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Key::new(event.code, event.modifiers)
+    }
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Space", KeyCode::Char(' ')),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The key itself may be '+', as in "+" or "ctrl++".
+        let (modifiers, key) = match s.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => (rest, "+"),
+            _ => match s.rsplit_once('+') {
+                Some((modifiers, key)) => (modifiers, key),
+                None => ("", s),
+            },
+        };
+        let mut parsed = KeyModifiers::NONE;
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            parsed |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{modifier}' in key '{s}'")),
+            };
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if parsed.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => named_key(key).ok_or_else(|| format!("unknown key '{key}'"))?,
+        };
+        let code = match code {
+            KeyCode::Tab if parsed.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            code => code,
+        };
+        Ok(Key::new(code, parsed))
+    }
+}
+
+fn named_key(name: &str) -> Option<KeyCode> {
+    if let Some(n) = name.strip_prefix(['f', 'F']) {
+        return n
+            .parse()
+            .ok()
+            .filter(|n| (1..=12).contains(n))
+            .map(KeyCode::F);
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, code)| *code)
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => match NAMED_KEYS.iter().find(|(_, named)| *named == code) {
+                Some((name, _)) => f.write_str(name),
+                None => write!(f, "{code:?}"),
+            },
+        }
+    }
+}
+
+/// Keys pressed one after the other, written separated by spaces like
+/// `g g` or `ctrl+x ctrl+c`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Chord(pub Vec<Key>);
+
+impl Chord {
+    /// Whether one of the chords starts with the other, so that they can't
+    /// both be bound.
+    pub fn overlaps(&self, other: &Chord) -> bool {
+        self.0.iter().zip(&other.0).all(|(a, b)| a == b)
+    }
+}
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<Key>, _>>()?;
+        if keys.is_empty() {
+            return Err("expected a key".into());
+        }
+        Ok(Chord(keys))
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, key) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{key}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of looking up the keys pressed so far in a [`KeyMap`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Lookup {
+    Action(Action),
+    /// The keys start a longer chord, wait for the next one.
+    Partial,
+    Unbound,
+}
+
+/// Which chords trigger which actions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KeyMap {
+    bindings: Vec<(Chord, Action)>,
+}
+
+impl KeyMap {
+    /// Makes `chords` the only bindings of `action`, taking them away from any
+    /// other action they overlap with.
+    pub fn bind(&mut self, action: Action, chords: Vec<Chord>) {
+        self.bindings.retain(|(bound, bound_action)| {
+            *bound_action != action && !chords.iter().any(|chord| chord.overlaps(bound))
+        });
+        self.bindings
+            .extend(chords.into_iter().map(|chord| (chord, action)));
+    }
+
+    pub fn bindings(&self) -> &[(Chord, Action)] {
+        &self.bindings
+    }
+
+    /// Chords bound to `action`.
+    pub fn chords(&self, action: Action) -> impl Iterator<Item = &Chord> {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(chord, _)| chord)
+    }
+
+    /// How to describe `action` in a hint, its first chord or `None` if
+    /// unbound.
+    pub fn hint(&self, action: Action) -> Option<&Chord> {
+        self.chords(action).next()
+    }
+
+    pub fn lookup(&self, pressed: &[Key]) -> Lookup {
+        let mut lookup = Lookup::Unbound;
+        for (chord, action) in &self.bindings {
+            if chord.0 == pressed {
+                return Lookup::Action(*action);
+            }
+            if chord.0.starts_with(pressed) {
+                lookup = Lookup::Partial;
+            }
+        }
+        lookup
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = [
+            ("s", Action::Start),
+            ("r", Action::Reset),
+            ("p", Action::Pause),
+            ("n", Action::Skip),
+            ("+", Action::Extend),
+            ("=", Action::Extend),
+            ("-", Action::Shorten),
+            ("_", Action::Shorten),
+            ("'", Action::Interrupt(InterruptionKind::Internal)),
+            ("e", Action::Interrupt(InterruptionKind::External)),
+            ("v", Action::Void),
+            ("Enter", Action::Confirm),
+            ("Tab", Action::Profiles),
+            ("l", Action::Tasks),
+            ("g", Action::Goal),
+            ("t", Action::Stats),
+            ("?", Action::Help),
+            ("q", Action::Quit),
+            ("ctrl+c", Action::Quit),
+        ];
+        Self {
+            bindings: bindings
+                .into_iter()
+                .map(|(chord, action)| (chord.parse().unwrap(), action))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(s: &str) -> Key {
+        s.parse().unwrap()
+    }
+
+    fn chord(s: &str) -> Chord {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_keys_with_modifiers() {
+        assert_eq!(key("s"), Key::new(KeyCode::Char('s'), KeyModifiers::NONE));
+        assert_eq!(key("+"), Key::new(KeyCode::Char('+'), KeyModifiers::NONE));
+        assert_eq!(
+            key("ctrl++"),
+            Key::new(KeyCode::Char('+'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(key("shift+s"), key("S"));
+        assert_eq!(key("enter"), Key::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(key("alt+F5"), Key::new(KeyCode::F(5), KeyModifiers::ALT));
+        assert!("hyper+s".parse::<Key>().is_err());
+        assert!("ctrl+nope".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn keys_display_as_parsed() {
+        for s in ["s", "ctrl+c", "Enter", "Space", "ctrl+alt+Up", "+", "F12"] {
+            assert_eq!(key(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn looks_up_chords() {
+        let mut keys = KeyMap::default();
+        keys.bind(Action::Skip, vec![chord("g n")]);
+        assert_eq!(keys.lookup(&[key("g")]), Lookup::Partial);
+        assert_eq!(
+            keys.lookup(&[key("g"), key("n")]),
+            Lookup::Action(Action::Skip)
+        );
+        assert_eq!(keys.lookup(&[key("n")]), Lookup::Unbound);
+        assert_eq!(keys.lookup(&[key("q")]), Lookup::Action(Action::Quit));
+    }
+
+    #[test]
+    fn default_bindings() {
+        let keys = KeyMap::default();
+        for (s, action) in [
+            ("+", Action::Extend),
+            ("=", Action::Extend),
+            ("-", Action::Shorten),
+            ("_", Action::Shorten),
+            ("'", Action::Interrupt(InterruptionKind::Internal)),
+            ("e", Action::Interrupt(InterruptionKind::External)),
+            ("v", Action::Void),
+            ("l", Action::Tasks),
+            ("g", Action::Goal),
+            ("t", Action::Stats),
+        ] {
+            assert_eq!(keys.lookup(&[key(s)]), Lookup::Action(action), "{s}");
+        }
+        assert_eq!(keys.hint(Action::Shorten), Some(&chord("-")));
+    }
+
+    #[test]
+    fn binding_takes_keys_from_other_actions() {
+        let mut keys = KeyMap::default();
+        keys.bind(Action::Start, vec![chord("q")]);
+        assert_eq!(keys.lookup(&[key("q")]), Lookup::Action(Action::Start));
+        assert_eq!(
+            keys.chords(Action::Quit).collect::<Vec<_>>(),
+            [&chord("ctrl+c")]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! Pomodoro timer engine behind the `pomotui` TUI.
+//!
+//! The state machine lives in [`App`]: create one from [`Settings`] and a
+//! [`Clock`], call [`App::start`] to begin the first work phase and
+//! [`App::update`] periodically to advance it. The timer runs through the
+//! phases of [`Settings::schedule`] and starts over after the last one, by
+//! default Work → Short break (repeated `work_cycles - 1` times) → Work →
+//! Long break.
+//!
+//! ```
+//! use pomotui::{App, ManualClock, Settings};
+//! use std::time::Duration;
+//!
+//! let clock = ManualClock::new();
+//! let mut app = App::new(Settings::default(), clock.clone());
+//! app.start();
+//! clock.advance(Duration::from_secs(25 * 60));
+//! app.update();
+//! assert_eq!(app.state().name(), "Short break");
+//! ```
+
+mod app;
+mod clock;
+mod config;
+mod duration;
+mod export;
+mod files;
+mod flow;
+mod history;
+mod json;
+mod keymap;
+mod localtime;
+mod options;
+mod phase;
+mod settings;
+mod snapshot;
+mod state;
+mod stats;
+mod tasks;
+mod toml;
+
+pub use app::App;
+pub use clock::{Clock, ManualClock, MonotonicClock};
+pub use config::{Config, ConfigError, ConfigWatcher};
+pub use duration::{format_duration, parse_duration};
+pub use export::{export, Format};
+pub use flow::FlowBreaks;
+pub use history::{History, Record, Reflection, SCHEMA_VERSION};
+pub use keymap::{Action, Chord, Key, KeyMap, Lookup};
+pub use localtime::{Date, TimeOfDay};
+pub use options::{Options, OptionsPatch, SettingsError, ValidationErrors};
+pub use phase::{format_color, parse_color, Phase, PhaseKind, Schedule};
+pub use settings::{AfterVoid, Settings};
+pub use snapshot::Snapshot;
+pub use state::{Interruption, InterruptionKind, PhaseOutcome, PomoState, Transition};
+pub use stats::{Day, Stats};
+pub use tasks::{Task, TaskList};
+
+/// Formats milliseconds as `MM:SS`.
+pub fn convert_millis_to_time(millis: u128) -> String {
+    let seconds = millis / 1000;
+    let minutes = seconds / 60;
+    format!("{:02}:{:02}", minutes, seconds % 60)
+}
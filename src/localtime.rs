@@ -0,0 +1,260 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// A wall-clock time of day, parsed from `HH:MM`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl TimeOfDay {
+    /// The first moment after `now` at this time of day in the local timezone.
+    pub fn next_after(&self, now: SystemTime) -> SystemTime {
+        let now_secs = unix_seconds(now);
+        let offset = utc_offset(now);
+        let local_midnight = (now_secs + offset).div_euclid(DAY) * DAY - offset;
+        let mut target = local_midnight + self.hour as i64 * 3600 + self.minute as i64 * 60;
+        if target <= now_secs {
+            target += DAY;
+        }
+        from_unix_seconds(target)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time '{s}', expected HH:MM");
+        let (hour, minute) = s.split_once(':').ok_or_else(invalid)?;
+        let hour: u8 = hour.parse().map_err(|_| invalid())?;
+        let minute: u8 = minute.parse().map_err(|_| invalid())?;
+        if hour > 23 || minute > 59 {
+            return Err(invalid());
+        }
+        Ok(Self { hour, minute })
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// A calendar date.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    /// The local date at `time`.
+    pub fn local(time: SystemTime) -> Self {
+        let secs = unix_seconds(time) + utc_offset(time);
+        Self::from_days(secs.div_euclid(DAY))
+    }
+
+    /// The date `days` days after 1970-01-01.
+    pub fn from_days(days: i64) -> Self {
+        // Howard Hinnant's civil_from_days.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+        Self { year, month, day }
+    }
+
+    /// Days since 1970-01-01.
+    pub fn days(&self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5
+            + i64::from(self.day)
+            - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// Local midnight at the start of the day.
+    pub fn start(&self) -> SystemTime {
+        let midnight = self.days() * DAY;
+        // The offset at midnight UTC is close enough to find local midnight.
+        from_unix_seconds(midnight - utc_offset(from_unix_seconds(midnight)))
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid date '{s}', expected YYYY-MM-DD");
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let year = next()?.parse().map_err(|_| invalid())?;
+        let month: u8 = next()?.parse().map_err(|_| invalid())?;
+        let day: u8 = next()?.parse().map_err(|_| invalid())?;
+        let date = Self { year, month, day };
+        if !(1..=12).contains(&month) || day == 0 || Date::from_days(date.days()) != date {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// `time` in local time as RFC 3339, e.g. `2024-05-01T09:30:00+02:00`.
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let offset = utc_offset(time);
+    let local = unix_seconds(time) + offset;
+    let date = Date::from_days(local.div_euclid(DAY));
+    let secs = local.rem_euclid(DAY);
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs() / 60;
+    format!(
+        "{date}T{:02}:{:02}:{:02}{sign}{:02}:{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        offset / 60,
+        offset % 60
+    )
+}
+
+/// Parses a time written by [`format_rfc3339`], or with a `Z` offset.
+pub fn parse_rfc3339(s: &str) -> Result<SystemTime, String> {
+    let invalid = || format!("invalid timestamp '{s}'");
+    let (date, rest) = s.split_once(['T', ' ']).ok_or_else(invalid)?;
+    let date: Date = date.parse()?;
+    let (time, offset) = match rest.strip_suffix(['Z', 'z']) {
+        Some(time) => (time, 0),
+        None => {
+            let at = rest.rfind(['+', '-']).ok_or_else(invalid)?;
+            let (time, offset) = rest.split_at(at);
+            let (hours, minutes) = offset[1..].split_once(':').ok_or_else(invalid)?;
+            let hours: i64 = hours.parse().map_err(|_| invalid())?;
+            let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+            let secs = hours * 3600 + minutes * 60;
+            (time, if offset.starts_with('-') { -secs } else { secs })
+        }
+    };
+    // Fractional seconds are ignored.
+    let time = time.split('.').next().unwrap_or(time);
+    let mut parts = time.splitn(3, ':');
+    let mut next = || -> Result<i64, String> {
+        parts
+            .next()
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())
+    };
+    let (hour, minute, second) = (next()?, next()?, next()?);
+    if hour > 23 || minute > 59 || second > 60 {
+        return Err(invalid());
+    }
+    Ok(from_unix_seconds(
+        date.days() * DAY + hour * 3600 + minute * 60 + second - offset,
+    ))
+}
+
+/// Seconds since the Unix epoch, negative before it.
+pub fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+pub fn from_unix_seconds(secs: i64) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+    }
+}
+
+/// Offset of the local timezone from UTC in seconds at `time`.
+#[cfg(unix)]
+pub fn utc_offset(time: SystemTime) -> i64 {
+    let secs = unix_seconds(time) as libc::time_t;
+    // SAFETY: `tm` is plain data and `localtime_r` only writes into it.
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&secs, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff as i64
+    }
+}
+
+/// Offset of the local timezone from UTC in seconds at `time`, always UTC
+/// where the timezone can't be looked up.
+#[cfg(not(unix))]
+pub fn utc_offset(_time: SystemTime) -> i64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> i64 {
+        unix_seconds(parse_rfc3339(s).unwrap())
+    }
+
+    #[test]
+    fn parses_offsets() {
+        let utc = 1_714_548_600;
+        assert_eq!(at("2024-05-01T07:30:00Z"), utc);
+        assert_eq!(at("2024-05-01T07:30:00+00:00"), utc);
+        assert_eq!(at("2024-05-01T09:30:00+02:00"), utc);
+        assert_eq!(at("2024-05-01T13:00:00+05:30"), utc);
+        assert_eq!(at("2024-04-30T23:30:00-08:00"), utc);
+        assert_eq!(at("2024-05-01 07:30:00.250z"), utc);
+        assert_eq!(at("1970-01-01T00:00:00+01:00"), -3600);
+    }
+
+    #[test]
+    fn rejects_bad_timestamps() {
+        for s in [
+            "",
+            "2024-05-01",
+            "2024-05-01T07:30:00",
+            "2024-05-01T07:30Z",
+            "2024-05-01T24:00:00Z",
+            "2024-05-01T07:30:00+0200",
+            "2024-05-01T07:30:00+xx:00",
+            "2024-13-01T07:30:00Z",
+        ] {
+            assert!(parse_rfc3339(s).is_err(), "{s:?} parsed");
+        }
+    }
+
+    #[test]
+    fn format_reads_back() {
+        for secs in [0, 1_714_548_600, 1_700_000_000] {
+            let time = from_unix_seconds(secs);
+            assert_eq!(parse_rfc3339(&format_rfc3339(time)), Ok(time));
+        }
+    }
+}
@@ -1,48 +1,640 @@
-use clap::{arg, Parser};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use pomotui::{
+    convert_millis_to_time, export, format_duration, parse_duration, Action, AfterVoid, App, Clock,
+    Config, ConfigError, ConfigWatcher, Date, FlowBreaks, Format, History, InterruptionKind, Key,
+    KeyMap, Lookup, MonotonicClock, OptionsPatch, PomoState, Record, Reflection, Schedule,
+    Settings, Snapshot, Stats, Task, TaskList, TimeOfDay, Transition,
+};
 use std::{
-    io,
-    time::{Duration, Instant},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
 };
 use tui::{
     backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    widgets::{
+        BarChart, Block, Borders, Clear, List, ListItem, ListState, Paragraph, Sparkline, Wrap,
+    },
     Frame, Terminal,
 };
 #[derive(Parser, Debug)]
 struct Args {
-    #[arg(short, long, default_value_t = 25)]
-    work_time: i64,
-    #[arg(short, long, default_value_t = 5)]
-    short_wait_time: i64,
-    #[arg(short, long, default_value_t = 20)]
-    long_wait_time: i64,
-    #[arg(short, long, default_value_t = 4)]
-    cycles: u32,
-    #[arg(long, default_value_t = false)]
-    dark_mode: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Config file to use instead of $XDG_CONFIG_HOME/pomotui/config.toml
+    #[arg(long, global = true, env = "POMOTUI_CONFIG")]
+    config: Option<PathBuf>,
+    /// File to record ended phases in instead of
+    /// $XDG_DATA_HOME/pomotui/history.jsonl
+    #[arg(long, global = true, env = "POMOTUI_HISTORY")]
+    history: Option<PathBuf>,
+    /// File to save the running session in instead of
+    /// $XDG_STATE_HOME/pomotui/session.json
+    #[arg(long, global = true, env = "POMOTUI_SESSION")]
+    session: Option<PathBuf>,
+    /// File to keep the task list in instead of
+    /// $XDG_DATA_HOME/pomotui/tasks.json
+    #[arg(long, global = true, env = "POMOTUI_TASKS")]
+    tasks: Option<PathBuf>,
+    /// Profile from the config file to start with
+    #[arg(long, env = "POMOTUI_PROFILE")]
+    profile: Option<String>,
+    /// Length of work phases, e.g. "25" (minutes), "25m", "90s", "1h15m" or "1:30" [default: 25m]
+    #[arg(short, long, env = "POMOTUI_WORK_TIME", value_parser = parse_duration, allow_hyphen_values = true)]
+    work_time: Option<Duration>,
+    /// Length of short breaks [default: 5m]
+    #[arg(short, long, env = "POMOTUI_SHORT_WAIT_TIME", value_parser = parse_duration, allow_hyphen_values = true)]
+    short_wait_time: Option<Duration>,
+    /// Length of the long break after the last cycle [default: 20m]
+    #[arg(short, long, env = "POMOTUI_LONG_WAIT_TIME", value_parser = parse_duration, allow_hyphen_values = true)]
+    long_wait_time: Option<Duration>,
+    /// Work phases before a long break [default: 4]
+    #[arg(short, long, env = "POMOTUI_CYCLES")]
+    cycles: Option<u32>,
+    #[arg(long, env = "POMOTUI_DARK_MODE", num_args = 0..=1, default_missing_value = "true", require_equals = true)]
+    dark_mode: Option<bool>,
+    /// Time added or removed by '+' and '-' [default: 5m]
+    #[arg(short, long, env = "POMOTUI_ADJUST_STEP", value_parser = parse_duration, allow_hyphen_values = true)]
+    adjust_step: Option<Duration>,
+    /// Keep counting past the end of a phase until confirmed with Enter
+    #[arg(long, env = "POMOTUI_OVERTIME", num_args = 0..=1, default_missing_value = "true", require_equals = true)]
+    overtime: Option<bool>,
+    /// Wait for Enter before starting each phase
+    #[arg(long, env = "POMOTUI_CONFIRM_TRANSITIONS", num_args = 0..=1, default_missing_value = "true", require_equals = true)]
+    confirm_transitions: Option<bool>,
+    /// Custom phases instead of the work/short/long cycle, e.g.
+    /// "work 50, break 10, work 90, break:Long_break 30 green"
+    #[arg(long, env = "POMOTUI_SCHEDULE")]
+    schedule: Option<Schedule>,
+    /// Stop after this many completed work phases
+    #[arg(long, env = "POMOTUI_SESSIONS")]
+    sessions: Option<u32>,
+    /// Don't start new phases after this time of day (HH:MM), in UTC on
+    /// systems other than Unix
+    #[arg(long, env = "POMOTUI_UNTIL")]
+    until: Option<TimeOfDay>,
+    /// Count work phases up and end them with Enter, followed by a break
+    /// based on how long you worked
+    #[arg(long, env = "POMOTUI_FLOWTIME", num_args = 0..=1, default_missing_value = "true", require_equals = true)]
+    flowtime: Option<bool>,
+    /// Flowtime breaks, "/5" for a fifth of the work time or a table of
+    /// "WORK=BREAK" durations like "25m=5m, 50m=8m, 90m=10m, 15m" [default: /5]
+    #[arg(long, env = "POMOTUI_FLOW_BREAKS")]
+    flow_breaks: Option<FlowBreaks>,
+    /// Actions to confirm while a phase is running, comma separated, or none
+    /// when given without a value [default: reset,quit]
+    #[arg(long, env = "POMOTUI_CONFIRM_ACTIONS", num_args = 0.., value_delimiter = ',', require_equals = true)]
+    confirm_actions: Option<Vec<Action>>,
+    /// Where to go after voiding a work phase, "menu" or "work" to start it
+    /// over [default: menu]
+    #[arg(long, env = "POMOTUI_AFTER_VOID")]
+    after_void: Option<AfterVoid>,
+    /// Ask what you got done after each completed work phase
+    #[arg(long, env = "POMOTUI_REFLECT", num_args = 0..=1, default_missing_value = "true", require_equals = true)]
+    reflect: Option<bool>,
+    /// Pomodoros to aim for each day, 0 for no goal
+    #[arg(long, env = "POMOTUI_DAILY_GOAL")]
+    daily_goal: Option<u32>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Print totals from the history file
+    Stats {
+        #[command(flatten)]
+        range: DateRange,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write the history file as CSV, JSON or iCalendar
+    Export {
+        /// csv, json or ics
+        #[arg(long, default_value = "csv")]
+        format: Format,
+        #[command(flatten)]
+        range: DateRange,
+        /// File to write instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct DateRange {
+    /// First day to include (YYYY-MM-DD) [default: the first recorded day]
+    #[arg(long)]
+    from: Option<Date>,
+    /// Last day to include (YYYY-MM-DD) [default: today]
+    #[arg(long)]
+    to: Option<Date>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Write a config file with every option commented out
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+impl Args {
+    fn config_path(&self) -> Option<PathBuf> {
+        self.config.clone().or_else(Config::default_path)
+    }
+
+    /// Reads the config file, a missing file is only an error if it was asked
+    /// for with --config.
+    fn load_config(&self) -> Result<Config, ConfigError> {
+        match self.config_path() {
+            Some(path) if self.config.is_some() || path.exists() => Config::load(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    fn history(&self) -> Option<History> {
+        self.history
+            .clone()
+            .or_else(History::default_path)
+            .map(History::new)
+    }
+
+    fn patch(&self) -> OptionsPatch {
+        OptionsPatch {
+            work_time: self.work_time,
+            short_wait_time: self.short_wait_time,
+            long_wait_time: self.long_wait_time,
+            cycles: self.cycles,
+            dark_mode: self.dark_mode,
+            adjust_step: self.adjust_step,
+            overtime: self.overtime,
+            confirm_transitions: self.confirm_transitions,
+            schedule: self.schedule.clone(),
+            sessions: self.sessions,
+            until: self.until,
+            flowtime: self.flowtime,
+            flow_breaks: self.flow_breaks.clone(),
+            confirm_actions: self.confirm_actions.clone(),
+            after_void: self.after_void,
+            reflect: self.reflect,
+            daily_goal: self.daily_goal,
+        }
+    }
+}
+
+/// Everything `Settings` are built from, kept around so they can be rebuilt
+/// for another profile.
+struct SettingsSource {
+    config: Config,
+    overrides: OptionsPatch,
+    profile: Option<String>,
+    /// When pomotui started. `until` is the next such time after it, so
+    /// rebuilding the settings once it has passed doesn't move it to the
+    /// next day.
+    started: SystemTime,
+}
+
+impl SettingsSource {
+    fn settings(&self) -> Result<Settings, String> {
+        let options = self
+            .config
+            .options(self.profile.as_deref(), &self.overrides)
+            .map_err(|error| error.to_string())?;
+        let mut settings = options
+            .into_settings(self.started)
+            .map_err(|errors| errors.to_string())?;
+        settings.profile = self.profile.clone();
+        Ok(settings)
+    }
+}
+
+/// A window drawn over the gauge that takes all key presses while open.
+enum Overlay {
+    Profiles(ProfilePicker),
+    Help,
+    /// Asks before carrying out an action listed in
+    /// `Settings::confirm_actions`.
+    Confirm(Action),
+    /// Offers to continue the session saved by a previous run.
+    Resume(Snapshot),
+    /// Totals from the history file, shown below the gauge.
+    Stats(Result<Stats, String>),
+    Tasks(TaskPane),
+    /// A line of text being typed in answer to a prompt.
+    Prompt(Prompt, String),
+    /// Asks what got done in the work phase that just completed, while the
+    /// break counts down behind it.
+    Reflect(ReflectionForm),
+}
+
+struct ReflectionForm {
+    /// When the work phase started, which identifies it in the history.
+    started: SystemTime,
+    /// Name of the work phase.
+    phase: String,
+    note: String,
+    /// From 1 to 5.
+    focus: Option<u8>,
+}
+
+impl ReflectionForm {
+    fn reflection(&self) -> Option<Reflection> {
+        let note = Some(self.note.trim().to_string()).filter(|note| !note.is_empty());
+        (note.is_some() || self.focus.is_some()).then_some(Reflection {
+            note,
+            focus: self.focus,
+        })
+    }
+}
+
+enum Prompt {
+    /// A note on the interruption logged at the given time, optional.
+    Note(InterruptionKind, SystemTime),
+    /// Why the work phase that started at the given time is voided, Esc
+    /// cancels.
+    VoidReason(SystemTime),
+    /// Pomodoros to aim for today, empty for no goal.
+    Goal,
+}
+
+/// The profile list opened with Tab in the menu.
+struct ProfilePicker {
+    profiles: Vec<String>,
+    state: ListState,
+    error: Option<String>,
+}
+
+impl ProfilePicker {
+    fn new(config: &Config, current: Option<&str>) -> Self {
+        let profiles: Vec<String> = config.profiles.keys().cloned().collect();
+        let mut state = ListState::default();
+        state.select(Some(
+            current
+                .and_then(|current| profiles.iter().position(|p| p == current))
+                .unwrap_or(0),
+        ));
+        Self {
+            profiles,
+            state,
+            error: None,
+        }
+    }
+
+    fn step(&mut self, by: isize) {
+        if self.profiles.is_empty() {
+            return;
+        }
+        let len = self.profiles.len() as isize;
+        let selected = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((selected + by).rem_euclid(len) as usize));
+    }
+
+    fn selected(&self) -> Option<&String> {
+        self.profiles.get(self.state.selected()?)
+    }
+}
+
+/// The task list opened with 'l', edits `App::tasks` directly.
+struct TaskPane {
+    state: ListState,
+    /// Name of the task being added.
+    input: Option<String>,
+}
+
+impl TaskPane {
+    fn new(tasks: &TaskList) -> Self {
+        let mut state = ListState::default();
+        state.select(Some(tasks.active_index().unwrap_or(0)));
+        Self { state, input: None }
+    }
+
+    /// Handles a key press, returns `true` once the pane should close.
+    fn handle(&mut self, code: KeyCode, tasks: &mut TaskList) -> bool {
+        if let Some(input) = &mut self.input {
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let name = input.trim().to_string();
+                    if !name.is_empty() {
+                        tasks.add(Task::new(name));
+                        self.state.select(Some(tasks.tasks().len() - 1));
+                    }
+                    self.input = None;
+                }
+                KeyCode::Esc => self.input = None,
+                _ => {}
+            }
+            return false;
+        }
+        let len = tasks.tasks().len();
+        let selected = self.state.selected().filter(|&i| i < len);
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.step(-1, len),
+            KeyCode::Down | KeyCode::Char('j') => self.step(1, len),
+            KeyCode::Char('a') => self.input = Some(String::new()),
+            KeyCode::Enter => {
+                if selected.is_some() && selected == tasks.active_index() {
+                    tasks.set_active(None);
+                } else {
+                    tasks.set_active(selected);
+                }
+            }
+            KeyCode::Char('x') => {
+                if let Some(i) = selected {
+                    tasks.set_done(i, !tasks.tasks()[i].done);
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if let Some(i) = selected {
+                    tasks.remove(i);
+                    self.state.select(Some(i.min(len.saturating_sub(2))));
+                }
+            }
+            KeyCode::Char('+' | '=') => {
+                if let Some(i) = selected {
+                    tasks.adjust_estimate(i, 1);
+                }
+            }
+            KeyCode::Char('-') => {
+                if let Some(i) = selected {
+                    tasks.adjust_estimate(i, -1);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return true,
+            _ => {}
+        }
+        false
+    }
+
+    fn step(&mut self, by: isize, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let selected = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((selected + by).rem_euclid(len as isize) as usize));
+    }
+}
+
+/// A message shown below the gauge.
+struct Status {
+    text: String,
+    error: bool,
+    shown_at: Instant,
+}
+
+impl Status {
+    /// How long a message that isn't an error stays up.
+    const TIMEOUT: Duration = Duration::from_secs(3);
+
+    fn info(text: String) -> Self {
+        Self {
+            text,
+            error: false,
+            shown_at: Instant::now(),
+        }
+    }
+
+    fn error(text: String) -> Self {
+        Self {
+            text,
+            error: true,
+            shown_at: Instant::now(),
+        }
+    }
+
+    fn expired(&self) -> bool {
+        !self.error && self.shown_at.elapsed() >= Self::TIMEOUT
+    }
+}
+
+/// Picks up changes to the config file, keeping the previous settings if the
+/// new file doesn't parse or validate.
+fn reload_config<C: Clock>(
+    watcher: &mut ConfigWatcher,
+    source: &mut SettingsSource,
+    app: &mut App<C>,
+) -> Option<Status> {
+    let config = match watcher.poll()? {
+        Ok(config) => config,
+        Err(error) => return Some(Status::error(error.to_string())),
+    };
+    let previous = std::mem::replace(&mut source.config, config);
+    match source.settings() {
+        Ok(settings) => {
+            app.set_settings(settings);
+            Some(Status::info(format!(
+                "Reloaded {}",
+                watcher.path().display()
+            )))
+        }
+        Err(error) => {
+            source.config = previous;
+            Some(Status::error(format!(
+                "{}: {}",
+                watcher.path().display(),
+                error
+            )))
+        }
+    }
 }
 
-fn get_sys_time() -> u128 {
-    let now = std::time::SystemTime::now();
-    now.duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis()
+fn exit_with_error(kind: ErrorKind, message: impl fmt::Display) -> ! {
+    Args::command().error(kind, message).exit()
 }
 
-fn convert_millis_to_time(millis: u128) -> String {
-    let seconds = millis / 1000;
-    let minutes = seconds / 60;
-    format!("{:02}:{:02}", minutes, seconds % 60)
+/// `pomotui config init`
+fn init_config(path: Option<PathBuf>, force: bool) -> io::Result<()> {
+    let Some(path) = path else {
+        exit_with_error(
+            ErrorKind::MissingRequiredArgument,
+            "no config directory found, pass a path with --config",
+        );
+    };
+    if path.exists() && !force {
+        exit_with_error(
+            ErrorKind::ValueValidation,
+            format!(
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            ),
+        );
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, Config::template())?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+/// Records in `range` from the history file, and the first and last day of
+/// the range.
+fn load_history(history: Option<History>, range: &DateRange) -> (Vec<Record>, Date, Date) {
+    let Some(history) = history else {
+        exit_with_error(
+            ErrorKind::MissingRequiredArgument,
+            "no history file found, pass one with --history",
+        );
+    };
+    let mut records = match history.load() {
+        Ok(records) => records,
+        Err(error) => exit_with_error(
+            ErrorKind::Io,
+            format!("{}: {}", history.path().display(), error),
+        ),
+    };
+    let to = range.to.unwrap_or_else(|| Date::local(SystemTime::now()));
+    if let Some(from) = range.from.filter(|from| *from > to) {
+        exit_with_error(
+            ErrorKind::ValueValidation,
+            format!("--from {from} is after --to {to}"),
+        );
+    }
+    let from = range
+        .from
+        .or_else(|| {
+            records
+                .iter()
+                .map(|record| Date::local(record.started))
+                .min()
+        })
+        .unwrap_or(to)
+        .min(to);
+    records.retain(|record| (from..=to).contains(&Date::local(record.started)));
+    (records, from, to)
+}
+
+/// `pomotui stats`
+fn print_stats(history: Option<History>, range: &DateRange, json: bool) -> io::Result<()> {
+    let (records, from, to) = load_history(history, range);
+    let stats = Stats::new(&records, to, (to.days() - from.days() + 1) as usize);
+    if json {
+        println!("{}", stats.to_json());
+        return Ok(());
+    }
+    println!("{from} to {to}");
+    println!("Pomodoros: {}", stats.pomodoros);
+    println!(
+        "Focused: {}",
+        format_duration(whole_minutes(stats.focus_time))
+    );
+    println!(
+        "Longest streak: {} day{}",
+        stats.longest_streak,
+        if stats.longest_streak == 1 { "" } else { "s" }
+    );
+    println!(
+        "Average break overrun: {}",
+        stats
+            .average_break_overrun
+            .map_or("-".to_string(), format_duration)
+    );
+    println!(
+        "Interruptions: {} internal, {} external",
+        stats.internal_interruptions, stats.external_interruptions
+    );
+    let days: Vec<_> = stats
+        .days
+        .iter()
+        .filter(|day| !day.focus_time.is_zero())
+        .collect();
+    if !days.is_empty() {
+        println!();
+        println!("Day         Pomodoros  Focused  Interruptions");
+    }
+    for day in days {
+        println!(
+            "{}  {:>9}  {:>7}  {:>13}",
+            day.date,
+            day.pomodoros,
+            format_duration(whole_minutes(day.focus_time)),
+            day.interruptions
+        );
+    }
+    Ok(())
+}
+
+/// `pomotui export`
+fn export_history(
+    history: Option<History>,
+    range: &DateRange,
+    format: Format,
+    output: Option<&Path>,
+) -> io::Result<()> {
+    let (records, _, _) = load_history(history, range);
+    let exported = export(&records, format);
+    match output {
+        Some(path) => fs::write(path, exported),
+        None => io::Write::write_all(&mut io::stdout(), exported.as_bytes()),
+    }
+}
+
+/// `duration` rounded down to whole minutes.
+fn whole_minutes(duration: Duration) -> Duration {
+    Duration::from_secs(duration.as_secs() / 60 * 60)
 }
 
 fn main() -> Result<(), io::Error> {
+    let args = Args::parse();
+    match &args.command {
+        Some(Command::Config {
+            command: ConfigCommand::Init { force },
+        }) => return init_config(args.config_path(), *force),
+        Some(Command::Stats { range, json }) => {
+            return print_stats(args.history(), range, *json);
+        }
+        Some(Command::Export {
+            format,
+            range,
+            output,
+        }) => return export_history(args.history(), range, *format, output.as_deref()),
+        None => {}
+    }
+
+    let source = SettingsSource {
+        config: match args.load_config() {
+            Ok(config) => config,
+            Err(error) => exit_with_error(ErrorKind::Io, error),
+        },
+        overrides: args.patch(),
+        profile: args.profile.clone(),
+        started: SystemTime::now(),
+    };
+    let settings = match source.settings() {
+        Ok(settings) => settings,
+        Err(error) => exit_with_error(ErrorKind::ValueValidation, error),
+    };
+
+    let tasks_path = args.tasks.clone().or_else(TaskList::default_path);
+    let tasks = match tasks_path.as_deref().map(TaskList::load) {
+        Some(Ok(tasks)) => tasks,
+        Some(Err(error)) => exit_with_error(
+            ErrorKind::Io,
+            format!("{}: {}", tasks_path.unwrap_or_default().display(), error),
+        ),
+        None => TaskList::default(),
+    };
+
     // setup terminal
-    let args = Parser::parse();
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -51,8 +643,27 @@ fn main() -> Result<(), io::Error> {
 
     // create app and run it
     let tick_rate = Duration::from_millis(500);
-    let app = App::new(args);
-    let res = run_app(&mut terminal, app, tick_rate);
+    let mut app = App::new(settings, MonotonicClock);
+    app.set_tasks(tasks.clone());
+    let files = Files {
+        watcher: args.config_path().map(ConfigWatcher::new),
+        history: args.history(),
+        reflection_due: None,
+        session: args.session.clone().or_else(Snapshot::default_path),
+        tasks: tasks_path,
+        saved_tasks: tasks,
+    };
+    // Pomodoros from earlier runs count towards today's goal.
+    if let Some(Ok(records)) = files.history.as_ref().map(History::load) {
+        let today = Date::local(SystemTime::now());
+        app.set_pomodoros_today(today, Stats::new(&records, today, 1).today);
+    }
+    let resume = files
+        .session
+        .as_deref()
+        .and_then(|path| Snapshot::load(path).ok().flatten())
+        .filter(|snapshot| snapshot.state.is_active());
+    let res = run_app(&mut terminal, app, source, files, resume, tick_rate);
 
     // restore terminal
     disable_raw_mode()?;
@@ -70,231 +681,801 @@ fn main() -> Result<(), io::Error> {
     Ok(())
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
-enum PomoState {
-    Menu,
-    Work { time_left: i64 },
-    ShortWait { time_left: i64 },
-    LongWait { time_left: i64 },
+/// Files kept up to date while the app runs.
+struct Files {
+    watcher: Option<ConfigWatcher>,
+    history: Option<History>,
+    /// A reflection to ask for once no other overlay is open.
+    reflection_due: Option<ReflectionForm>,
+    /// Where the running session is saved for [`Overlay::Resume`].
+    session: Option<PathBuf>,
+    tasks: Option<PathBuf>,
+    /// The task list as last saved, it is only written when it changes.
+    saved_tasks: TaskList,
 }
 
-impl PomoState {
-    fn get_inner(&self) -> Option<i64> {
-        match self {
-            PomoState::Menu => None,
-            PomoState::Work { time_left } => Some(*time_left),
-            PomoState::ShortWait { time_left } => Some(*time_left),
-            PomoState::LongWait { time_left } => Some(*time_left),
-        }
-    }
-}
+/// How often the running session is saved.
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
 
-struct Settings {
-    work_time: i64,
-    short_wait_time: i64,
-    long_wait_time: i64,
-    work_cycles: u32,
-    dark_mode: bool,
+/// Saves the session so it can be resumed after a crash, or removes the saved
+/// one once there is nothing left to resume.
+fn save_session<C: Clock>(app: &App<C>, path: Option<&Path>) -> Result<(), String> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let saved = if app.state().is_active() {
+        app.snapshot().save(path)
+    } else {
+        Snapshot::remove(path)
+    };
+    saved.map_err(|error| format!("{}: {}", path.display(), error))
 }
 
-impl Settings {
-    fn new(args: Args) -> Self {
-        Self {
-            work_time: args.work_time * 60 * 1000,
-            short_wait_time: args.short_wait_time * 60 * 1000,
-            long_wait_time: args.long_wait_time * 60 * 1000,
-            work_cycles: args.cycles,
-            dark_mode: args.dark_mode,
+/// Appends the phases in `ended` to the history file. With
+/// `settings.reflect` a completed work phase is also asked about, see
+/// [`Overlay::Reflect`].
+fn save_history(
+    ended: &[Transition],
+    settings: &Settings,
+    files: &mut Files,
+) -> Result<(), String> {
+    let records: Vec<Record> = ended.iter().filter_map(Record::from_transition).collect();
+    let Some(history) = &files.history else {
+        return Ok(());
+    };
+    if let Some(record) = records.iter().rev().find(|record| record.can_reflect()) {
+        if settings.reflect {
+            files.reflection_due = Some(ReflectionForm {
+                started: record.started,
+                phase: record.name.clone(),
+                note: String::new(),
+                focus: None,
+            });
         }
     }
+    history
+        .append(&records)
+        .map_err(|error| format!("{}: {}", history.path().display(), error))
 }
 
-struct App {
-    state: PomoState,
-    settings: Settings,
-    cycle: Option<u32>,
-    last_update_time: u128,
-    paused: bool,
+/// Adds the answers in `form`, if any, to the history file.
+fn save_reflection(form: &ReflectionForm, history: Option<&History>) -> Result<(), String> {
+    match (form.reflection(), history) {
+        (Some(reflection), Some(history)) => history
+            .append_reflection(form.started, &reflection)
+            .map_err(|error| format!("{}: {}", history.path().display(), error)),
+        _ => Ok(()),
+    }
 }
 
-impl App {
-    fn new(args: Args) -> Self {
-        Self {
-            state: PomoState::Menu,
-            settings: Settings::new(args),
-            cycle: None,
-            last_update_time: get_sys_time(),
-            paused: false,
-        }
+/// Writes the task list if it changed since it was last saved.
+fn save_tasks<C: Clock>(app: &App<C>, files: &mut Files) -> Result<(), String> {
+    let Some(path) = &files.tasks else {
+        return Ok(());
+    };
+    if app.tasks() == &files.saved_tasks {
+        return Ok(());
     }
+    app.tasks()
+        .save(path)
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+    files.saved_tasks = app.tasks().clone();
+    Ok(())
+}
 
-    fn start(&mut self) {
-        self.state = PomoState::Work {
-            time_left: self.settings.work_time,
-        };
-        self.cycle = Some(self.settings.work_cycles);
-        self.last_update_time = get_sys_time();
-    }
+/// Saves what is left to save before leaving `run_app`.
+fn quit<C: Clock>(app: &mut App<C>, files: &mut Files) -> io::Result<()> {
+    save_history(&app.drain_transitions(), app.settings(), files).map_err(io::Error::other)?;
+    save_tasks(app, files).map_err(io::Error::other)?;
+    save_session(app, files.session.as_deref()).map_err(io::Error::other)
+}
 
-    fn update(&mut self) {
-        let time = get_sys_time();
-        let delta = (time - self.last_update_time) as i64;
-        self.last_update_time = time;
-        if self.paused {
-            return;
+/// Carries out `action`, returns `true` if the app should quit.
+fn perform<C: Clock>(
+    action: Action,
+    app: &mut App<C>,
+    source: &SettingsSource,
+    history: Option<&History>,
+    overlay: &mut Option<Overlay>,
+) -> bool {
+    match action {
+        Action::Start => {
+            if !app.state().is_active() {
+                app.start();
+            }
         }
-
-        let inner_time = match self.state.get_inner() {
-            Some(i) => i,
-            _ => return,
-        };
-        let new_inner_time = inner_time - delta;
-
-        // just update the timer
-        if new_inner_time.is_positive() {
-            self.state = match self.state {
-                PomoState::Menu => unreachable!(),
-                PomoState::Work { time_left: _ } => PomoState::Work {
-                    time_left: new_inner_time,
-                },
-                PomoState::ShortWait { time_left: _ } => PomoState::ShortWait {
-                    time_left: new_inner_time,
-                },
-                PomoState::LongWait { time_left: _ } => PomoState::LongWait {
-                    time_left: new_inner_time,
-                },
-            };
-            return;
+        Action::Reset => {
+            app.abandon();
+            app.start();
         }
-
-        // update the state
-        match (self.state.clone(), self.cycle) {
-            (PomoState::Work { time_left: _ }, Some(1)) => {
-                self.state = PomoState::LongWait {
-                    time_left: self.settings.long_wait_time,
-                };
-            }
-            (PomoState::Work { time_left: _ }, Some(_)) => {
-                self.state = PomoState::ShortWait {
-                    time_left: self.settings.short_wait_time,
-                };
-            }
-            (PomoState::ShortWait { time_left: _ }, Some(i)) => {
-                self.state = PomoState::Work {
-                    time_left: self.settings.work_time,
-                };
-                self.cycle = Some(i - 1);
-            }
-            (PomoState::LongWait { time_left: _ }, _) => {
-                self.state = PomoState::Work {
-                    time_left: self.settings.work_time,
-                };
-                self.cycle = Some(self.settings.work_cycles);
+        Action::Pause => app.toggle_pause(),
+        Action::Skip => app.skip(),
+        Action::Extend => app.extend(),
+        Action::Shorten => app.shorten(),
+        Action::Void => {
+            if app.is_working() {
+                *overlay = Some(Overlay::Prompt(
+                    Prompt::VoidReason(app.phase_started()),
+                    String::new(),
+                ));
             }
-            _ => unreachable!(),
         }
-    }
-
-    fn get_state_text(&self) -> String {
-        if self.paused {
-            return "PAUSED".into();
-        };
-        match self.state {
-            PomoState::Menu => "Press 's' to start, 'q' to quit, 'p' to pause".into(),
-            PomoState::Work { time_left } => format!(
-                "Work: {} - Cycle {}/{}",
-                convert_millis_to_time(time_left as u128),
-                self.settings.work_cycles - self.cycle.unwrap() + 1,
-                self.settings.work_cycles
-            ),
-            PomoState::ShortWait { time_left } => format!(
-                "Short break: {} - Cycle {}/{}",
-                convert_millis_to_time(time_left as u128),
-                self.settings.work_cycles - self.cycle.unwrap() + 1,
-                self.settings.work_cycles
-            ),
-            PomoState::LongWait { time_left } => {
-                format!("Long break: {}", convert_millis_to_time(time_left as u128))
+        Action::Interrupt(kind) => {
+            if let Some(at) = app.interrupt(kind) {
+                *overlay = Some(Overlay::Prompt(Prompt::Note(kind, at), String::new()));
             }
         }
-    }
-
-    fn get_ratio(&self) -> f64 {
-        if self.paused {
-            return 1.;
-        };
-        match self.state {
-            PomoState::Menu => 0.,
-            PomoState::Work { time_left } => time_left as f64 / self.settings.work_time as f64,
-            PomoState::ShortWait { time_left } => {
-                time_left as f64 / self.settings.short_wait_time as f64
-            }
-            PomoState::LongWait { time_left } => {
-                time_left as f64 / self.settings.long_wait_time as f64
+        Action::Confirm => app.confirm(),
+        Action::Profiles => {
+            if let PomoState::Menu = app.state() {
+                *overlay = Some(Overlay::Profiles(ProfilePicker::new(
+                    &source.config,
+                    source.profile.as_deref(),
+                )));
             }
         }
-    }
-
-    fn get_color(&self) -> tui::style::Color {
-        let ratio = self.get_ratio();
-        match self.state {
-            PomoState::Menu => Color::Gray,
-            PomoState::Work { .. } => {
-                Color::Rgb((ratio * 255.) as u8, 255 - (ratio * 255.) as u8, 0)
-            }
-            PomoState::ShortWait { .. } => Color::LightBlue,
-            PomoState::LongWait { .. } => Color::LightGreen,
+        Action::Tasks => *overlay = Some(Overlay::Tasks(TaskPane::new(app.tasks()))),
+        Action::Goal => {
+            let goal = app.settings().daily_goal;
+            *overlay = Some(Overlay::Prompt(
+                Prompt::Goal,
+                goal.map_or(String::new(), |goal| goal.to_string()),
+            ));
+        }
+        Action::Stats => {
+            let stats = match history {
+                Some(history) => history
+                    .load()
+                    .map(|records| Stats::new(&records, Date::local(SystemTime::now()), STATS_DAYS))
+                    .map_err(|error| format!("{}: {}", history.path().display(), error)),
+                None => Err("no history file, pass one with --history".into()),
+            };
+            *overlay = Some(Overlay::Stats(stats));
+        }
+        Action::Help => *overlay = Some(Overlay::Help),
+        Action::Quit => {
+            app.abandon();
+            return true;
         }
     }
+    false
 }
 
-fn run_app<B: Backend>(
+fn run_app<B: Backend, C: Clock>(
     terminal: &mut Terminal<B>,
-    mut app: App,
+    mut app: App<C>,
+    mut source: SettingsSource,
+    mut files: Files,
+    resume: Option<Snapshot>,
     tick_rate: Duration,
 ) -> io::Result<()> {
     let mut last_tick = Instant::now();
+    let mut last_save = Instant::now();
+    let mut save_now = false;
+    let mut overlay: Option<Overlay> = resume.map(Overlay::Resume);
+    let mut status: Option<Status> = None;
+    // Keys of a chord typed so far.
+    let mut pressed: Vec<Key> = Vec::new();
     loop {
-        terminal.draw(|f| ui(f, &app))?;
+        let ended = app.drain_transitions();
+        // The saved session has to move on with the history, or resuming it
+        // would record the same phases again.
+        save_now |= !ended.is_empty();
+        if let Err(error) = save_history(&ended, app.settings(), &mut files) {
+            status = Some(Status::error(error));
+        }
+        if overlay.is_none() {
+            overlay = files.reflection_due.take().map(Overlay::Reflect);
+        }
+        if let Err(error) = save_tasks(&app, &mut files) {
+            status = Some(Status::error(error));
+        }
+        // Until it is answered the saved session must stay as it is.
+        if !matches!(overlay, Some(Overlay::Resume(_)))
+            && (save_now || last_save.elapsed() >= SAVE_INTERVAL)
+        {
+            if let Err(error) = save_session(&app, files.session.as_deref()) {
+                status = Some(Status::error(error));
+            }
+            last_save = Instant::now();
+            save_now = false;
+        }
+        terminal.draw(|f| ui(f, &app, overlay.as_mut(), status.as_ref()))?;
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
-                if let KeyCode::Char('q') = key.code {
-                    return Ok(());
+                if key.kind != KeyEventKind::Press {
+                    continue;
                 }
-                if let KeyCode::Char('s') = key.code {
-                    app.start();
+                match &mut overlay {
+                    Some(Overlay::Profiles(open)) => {
+                        match key.code {
+                            KeyCode::Up | KeyCode::Char('k') => open.step(-1),
+                            KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => open.step(1),
+                            KeyCode::Esc | KeyCode::Char('q') => overlay = None,
+                            KeyCode::Enter => {
+                                let previous = source.profile.clone();
+                                source.profile = open.selected().cloned();
+                                match source.settings() {
+                                    Ok(settings) => {
+                                        app.set_settings(settings);
+                                        overlay = None;
+                                    }
+                                    Err(error) => {
+                                        source.profile = previous;
+                                        open.error = Some(error);
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    Some(Overlay::Tasks(pane)) => {
+                        if pane.handle(key.code, app.tasks_mut()) {
+                            overlay = None;
+                        }
+                        continue;
+                    }
+                    Some(Overlay::Prompt(prompt, text)) => {
+                        match key.code {
+                            // Only digits for the goal.
+                            KeyCode::Char(c)
+                                if !matches!(prompt, Prompt::Goal) || c.is_ascii_digit() =>
+                            {
+                                text.push(c)
+                            }
+                            KeyCode::Backspace => {
+                                text.pop();
+                            }
+                            KeyCode::Enter => {
+                                let text =
+                                    Some(text.trim().to_string()).filter(|text| !text.is_empty());
+                                match (prompt, text) {
+                                    (Prompt::Note(_, at), Some(note)) => {
+                                        if !app.note_interruption(*at, note.as_str()) {
+                                            status = Some(Status::error(format!(
+                                                "The phase ended before the note was added: {note}"
+                                            )));
+                                        }
+                                    }
+                                    (Prompt::Note(..), None) => {}
+                                    (Prompt::VoidReason(started), reason) => {
+                                        if app.void(*started, reason) {
+                                            save_now = true;
+                                        } else {
+                                            status = Some(Status::error(
+                                                "The phase ended before it was voided".into(),
+                                            ));
+                                        }
+                                    }
+                                    (Prompt::Goal, goal) => {
+                                        match goal.map_or(Ok(0), |goal| goal.parse()) {
+                                            Ok(goal) => {
+                                                // Kept over the config file like a command line
+                                                // flag, so reloading it doesn't undo it.
+                                                source.overrides.daily_goal = Some(goal);
+                                                match source.settings() {
+                                                    Ok(settings) => app.set_settings(settings),
+                                                    Err(error) => {
+                                                        status = Some(Status::error(error))
+                                                    }
+                                                }
+                                            }
+                                            Err(error) => {
+                                                status = Some(Status::error(format!(
+                                                    "Daily goal: {error}"
+                                                )))
+                                            }
+                                        }
+                                    }
+                                }
+                                overlay = None;
+                            }
+                            KeyCode::Esc => overlay = None,
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    Some(Overlay::Reflect(form)) => {
+                        match key.code {
+                            KeyCode::Char(c) => form.note.push(c),
+                            KeyCode::Backspace => {
+                                form.note.pop();
+                            }
+                            KeyCode::Right | KeyCode::Up => {
+                                form.focus = Some(form.focus.map_or(1, |focus| (focus + 1).min(5)));
+                            }
+                            KeyCode::Left | KeyCode::Down => {
+                                form.focus =
+                                    form.focus.filter(|&focus| focus > 1).map(|focus| focus - 1);
+                            }
+                            KeyCode::Enter => {
+                                if let Err(error) = save_reflection(form, files.history.as_ref()) {
+                                    status = Some(Status::error(error));
+                                }
+                                overlay = None;
+                            }
+                            KeyCode::Esc => overlay = None,
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    Some(Overlay::Help | Overlay::Stats(_)) => {
+                        overlay = None;
+                        continue;
+                    }
+                    Some(Overlay::Confirm(action)) => {
+                        let action = *action;
+                        overlay = None;
+                        if let KeyCode::Char('y' | 'Y') | KeyCode::Enter = key.code {
+                            if perform(
+                                action,
+                                &mut app,
+                                &source,
+                                files.history.as_ref(),
+                                &mut overlay,
+                            ) {
+                                return quit(&mut app, &mut files);
+                            }
+                        }
+                        save_now = true;
+                        continue;
+                    }
+                    Some(Overlay::Resume(_)) => {
+                        let Some(Overlay::Resume(snapshot)) = overlay.take() else {
+                            unreachable!()
+                        };
+                        if let KeyCode::Char('y' | 'Y') | KeyCode::Enter = key.code {
+                            app.restore(snapshot);
+                        }
+                        save_now = true;
+                        continue;
+                    }
+                    None => {}
                 }
-                if let KeyCode::Char('p') = key.code {
-                    app.paused = !app.paused;
+
+                let key = Key::from(key);
+                pressed.push(key);
+                let keys = &app.settings().keys;
+                let mut lookup = keys.lookup(&pressed);
+                if lookup == Lookup::Unbound && pressed.len() > 1 {
+                    // Not a chord after all, the last key may start a new one.
+                    pressed = vec![key];
+                    lookup = keys.lookup(&pressed);
+                }
+                let action = match lookup {
+                    Lookup::Action(action) => action,
+                    Lookup::Partial => continue,
+                    Lookup::Unbound => {
+                        pressed.clear();
+                        continue;
+                    }
+                };
+                pressed.clear();
+                if app.settings().confirm_actions.contains(&action) && app.state().is_active() {
+                    overlay = Some(Overlay::Confirm(action));
+                } else if perform(
+                    action,
+                    &mut app,
+                    &source,
+                    files.history.as_ref(),
+                    &mut overlay,
+                ) {
+                    return quit(&mut app, &mut files);
                 }
+                save_now = true;
             }
         }
         if last_tick.elapsed() >= tick_rate {
+            if let Some(watcher) = &mut files.watcher {
+                if let Some(reloaded) = reload_config(watcher, &mut source, &mut app) {
+                    status = Some(reloaded);
+                }
+            }
+            if status.as_ref().is_some_and(Status::expired) {
+                status = None;
+            }
             app.update();
             last_tick = Instant::now();
         }
     }
 }
 
-fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+fn ui<B: Backend, C: Clock>(
+    f: &mut Frame<B>,
+    app: &App<C>,
+    overlay: Option<&mut Overlay>,
+    status: Option<&Status>,
+) {
     let (message, ratio) = (app.get_state_text(), app.get_ratio());
-    let size = f.size();
+    let mut size = f.size();
+    if let Some(status) = status {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(size);
+        size = chunks[0];
+        let style = if status.error {
+            Style::default().fg(Color::White).bg(Color::Red)
+        } else {
+            Style::default()
+        };
+        f.render_widget(Paragraph::new(status.text.as_str()).style(style), chunks[1]);
+    }
+    let background = if app.settings().dark_mode {
+        Color::Black
+    } else {
+        Color::White
+    };
+    if let Some((done, goal)) = app.daily_progress() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(size);
+        size = chunks[0];
+        let progress = tui::widgets::Gauge::default()
+            .label(format!("{done}/{goal} today"))
+            .gauge_style(Style::default().fg(Color::LightGreen).bg(background))
+            .ratio((f64::from(done) / f64::from(goal)).min(1.));
+        f.render_widget(progress, chunks[1]);
+    }
     let color = app.get_color();
     let gauge = tui::widgets::Gauge::default()
         .label(message)
         .gauge_style(
             Style::fg(Style::default(), color)
-                .bg(if app.settings.dark_mode {
-                    Color::Black
-                } else {
-                    Color::White
-                })
+                .bg(background)
                 .add_modifier(Modifier::empty()),
         )
         .ratio(ratio);
-    f.render_widget(gauge, size);
+    if let Some(Overlay::Stats(stats)) = &overlay {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(size);
+        f.render_widget(gauge, chunks[0]);
+        render_stats(f, chunks[1], stats);
+    } else {
+        f.render_widget(gauge, size);
+    }
+
+    match overlay {
+        Some(Overlay::Profiles(picker)) => render_picker(f, picker),
+        Some(Overlay::Help) => render_help(f, &app.settings().keys),
+        Some(Overlay::Confirm(action)) => render_confirm(f, *action, app.state()),
+        Some(Overlay::Resume(snapshot)) => render_resume(f, snapshot, app.settings()),
+        Some(Overlay::Stats(_)) => {}
+        Some(Overlay::Tasks(pane)) => render_tasks(f, pane, app.tasks()),
+        Some(Overlay::Prompt(prompt, text)) => render_prompt(f, prompt, text, app.state()),
+        Some(Overlay::Reflect(form)) => render_reflection(f, form),
+        None => {}
+    }
+}
+
+fn render_picker<B: Backend>(f: &mut Frame<B>, picker: &mut ProfilePicker) {
+    let items: Vec<ListItem> = if picker.profiles.is_empty() {
+        vec![ListItem::new(
+            "No profiles, add [profiles.NAME] to the config file",
+        )]
+    } else {
+        picker
+            .profiles
+            .iter()
+            .map(|profile| ListItem::new(profile.as_str()))
+            .collect()
+    };
+    let title = match &picker.error {
+        Some(error) => format!(" {error} "),
+        None => " Profiles - Enter to pick, Esc to cancel ".into(),
+    };
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let area = centered(f.size(), 60, picker.profiles.len().max(1) as u16 + 2);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut picker.state);
+}
+
+fn render_tasks<B: Backend>(f: &mut Frame<B>, pane: &mut TaskPane, tasks: &TaskList) {
+    let mut items: Vec<ListItem> = tasks
+        .tasks()
+        .iter()
+        .enumerate()
+        .map(|(i, task)| {
+            let marker = if task.done {
+                "x"
+            } else if tasks.active_index() == Some(i) {
+                ">"
+            } else {
+                " "
+            };
+            let estimate = task.estimate.map_or("-".to_string(), |e| e.to_string());
+            let item = ListItem::new(format!(
+                "{marker} {:<56} {:>3}/{estimate}",
+                task.name, task.pomodoros
+            ));
+            if task
+                .estimate
+                .is_some_and(|estimate| task.pomodoros > estimate)
+            {
+                item.style(Style::default().fg(Color::LightRed))
+            } else {
+                item
+            }
+        })
+        .collect();
+    let mut state = pane.state.clone();
+    let title = match &pane.input {
+        Some(input) => {
+            items.push(ListItem::new(format!("+ {input}_")));
+            state.select(Some(items.len() - 1));
+            " New task - Enter to add, Esc to cancel "
+        }
+        None if items.is_empty() => {
+            items.push(ListItem::new("No tasks, press 'a' to add one"));
+            " Tasks "
+        }
+        None => " Tasks - Enter: make active, a: add, x: done, d: delete, +/-: estimate ",
+    };
+    let height = items.len() as u16 + 2;
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let area = centered(f.size(), 76, height);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+fn render_prompt<B: Backend>(f: &mut Frame<B>, prompt: &Prompt, text: &str, state: &PomoState) {
+    let title = match prompt {
+        Prompt::Note(InterruptionKind::Internal, _) => {
+            " Internal interruption logged - add a note and press Enter, or Esc to skip ".into()
+        }
+        Prompt::Note(InterruptionKind::External, _) => {
+            " External interruption logged - add a note and press Enter, or Esc to skip ".into()
+        }
+        Prompt::VoidReason(_) => format!(
+            " Void {}? - give a reason and press Enter, or Esc to cancel ",
+            state.name()
+        ),
+        Prompt::Goal => {
+            " Daily goal in pomodoros - Enter to set, empty for none, Esc to cancel ".into()
+        }
+    };
+    let paragraph = Paragraph::new(format!("{text}_"))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    let area = centered(f.size(), 76, 3);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn render_reflection<B: Backend>(f: &mut Frame<B>, form: &ReflectionForm) {
+    let focus = form.focus.unwrap_or(0) as usize;
+    let text = format!(
+        "{}_\n\nFocus: {}{}  Left/Right to rate, Enter to save, Esc to skip",
+        form.note,
+        "●".repeat(focus),
+        "○".repeat(5 - focus)
+    );
+    let paragraph = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} done - what did you get done? ", form.phase)),
+    );
+    let area = centered(f.size(), 76, 5);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn render_help<B: Backend>(f: &mut Frame<B>, keys: &KeyMap) {
+    let items: Vec<ListItem> = Action::ALL
+        .into_iter()
+        .map(|action| {
+            let chords: Vec<String> = keys.chords(action).map(|c| c.to_string()).collect();
+            let chords = if chords.is_empty() {
+                "-".to_string()
+            } else {
+                chords.join(", ")
+            };
+            ListItem::new(format!("{chords:<16} {}", action.description()))
+        })
+        .collect();
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Keys - press any key to close "),
+    );
+    let area = centered(f.size(), 44, Action::ALL.len() as u16 + 2);
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
+fn render_confirm<B: Backend>(f: &mut Frame<B>, action: Action, state: &PomoState) {
+    let running = match state.phase() {
+        Some(phase) => format!(
+            "{} is still going and will be recorded as abandoned.",
+            phase.name
+        ),
+        None => "The session is still going.".to_string(),
+    };
+    let text = format!(
+        "{running} Press y to {}, any other key to cancel.",
+        action.description()
+    );
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Really {}? ", action.description())),
+    );
+    let area = centered(f.size(), 50, 5);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn render_resume<B: Backend>(f: &mut Frame<B>, snapshot: &Snapshot, settings: &Settings) {
+    let away = SystemTime::now()
+        .duration_since(snapshot.saved_at)
+        .unwrap_or_default();
+    let ago = match away.as_secs() / 60 {
+        0 => "a moment".to_string(),
+        minutes => format_duration(Duration::from_secs(minutes * 60)),
+    };
+    let mut text = match &snapshot.state {
+        PomoState::Running { phase, time_left } if !snapshot.paused => format!(
+            "{} had {} left {ago} ago.",
+            phase.name,
+            convert_millis_to_time((*time_left).max(0) as u128),
+        ),
+        state if snapshot.paused => format!("{} was paused {ago} ago.", state.name()),
+        state => format!("{} was running {ago} ago.", state.name()),
+    };
+    if snapshot.fingerprint != settings.fingerprint() {
+        text.push_str(" The timer settings have changed since.");
+    }
+    text.push_str(" Press y to resume, any other key to start fresh.");
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Resume previous session? "),
+    );
+    let area = centered(f.size(), 50, 6);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// Days of history shown in the stats view, the bar chart shows the last
+/// `STATS_BARS` of them.
+const STATS_DAYS: usize = 30;
+const STATS_BARS: usize = 14;
+
+fn render_stats<B: Backend>(f: &mut Frame<B>, area: Rect, stats: &Result<Stats, String>) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Statistics - press any key to close ");
+    let stats = match stats {
+        Ok(stats) => stats,
+        Err(error) => {
+            f.render_widget(
+                Paragraph::new(error.as_str())
+                    .wrap(Wrap { trim: true })
+                    .block(block),
+                area,
+            );
+            return;
+        }
+    };
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(4),
+            Constraint::Length(4),
+        ])
+        .split(inner);
+
+    let summary = format!(
+        "Today: {}   This week: {}   All time: {}\n\
+         Focused: {}   Longest streak: {} days   Average break overrun: {}\n\
+         Interruptions: {} internal, {} external",
+        stats.today,
+        stats.this_week,
+        stats.pomodoros,
+        format_duration(whole_minutes(stats.focus_time)),
+        stats.longest_streak,
+        stats
+            .average_break_overrun
+            .map_or("-".to_string(), format_duration),
+        stats.internal_interruptions,
+        stats.external_interruptions,
+    );
+    f.render_widget(Paragraph::new(summary).wrap(Wrap { trim: true }), chunks[0]);
+
+    let bars = &stats.days[stats.days.len().saturating_sub(STATS_BARS)..];
+    let labels: Vec<String> = bars
+        .iter()
+        .map(|day| format!("{:02}", day.date.day))
+        .collect();
+    let data: Vec<(&str, u64)> = labels
+        .iter()
+        .zip(bars)
+        .map(|(label, day)| (label.as_str(), u64::from(day.pomodoros)))
+        .collect();
+    let bar_width = (chunks[1].width / bars.len().max(1) as u16)
+        .saturating_sub(1)
+        .max(2);
+    let chart = BarChart::default()
+        .block(Block::default().title("Pomodoros per day"))
+        .data(&data)
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::LightRed))
+        .value_style(Style::default().fg(Color::Black).bg(Color::LightRed));
+    f.render_widget(chart, chunks[1]);
+
+    let focus: Vec<u64> = stats
+        .days
+        .iter()
+        .map(|day| day.focus_time.as_secs() / 60)
+        .collect();
+    let sparkline = Sparkline::default()
+        .block(Block::default().title(format!("Minutes focused, last {STATS_DAYS} days")))
+        .data(&focus)
+        .style(Style::default().fg(Color::LightGreen));
+    f.render_widget(sparkline, chunks[2]);
+}
+
+/// A rectangle of at most `width` by `height` in the middle of `area`.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn flags_beat_the_environment_which_beats_the_config() {
+        let config = Config::parse("work_time = 50\ncycles = 3\nsessions = 6").unwrap();
+        env::set_var("POMOTUI_CYCLES", "5");
+        env::set_var("POMOTUI_SESSIONS", "7");
+        let args = Args::try_parse_from(["pomotui", "--work-time", "30", "--sessions", "8"]);
+        env::remove_var("POMOTUI_CYCLES");
+        env::remove_var("POMOTUI_SESSIONS");
+        let options = config.options(None, &args.unwrap().patch()).unwrap();
+        assert_eq!(options.work_time, Duration::from_secs(30 * 60));
+        assert_eq!(options.cycles, 5);
+        assert_eq!(options.sessions, Some(8));
+    }
+
+    #[test]
+    fn rebuilt_settings_keep_the_deadline() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let source = SettingsSource {
+            config: Config::default(),
+            overrides: OptionsPatch {
+                until: Some("17:30".parse().unwrap()),
+                ..OptionsPatch::default()
+            },
+            profile: None,
+            started: SystemTime::now() - day,
+        };
+        // Still the deadline of the day pomotui started on, now passed.
+        let until = source.settings().unwrap().until.unwrap();
+        assert!(until < SystemTime::now());
+        assert!(until > source.started);
+    }
 }
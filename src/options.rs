@@ -0,0 +1,451 @@
+use std::{
+    error::Error,
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use crate::{localtime::TimeOfDay, Action, AfterVoid, FlowBreaks, KeyMap, Schedule, Settings};
+
+/// Timer options as given on the command line or in a config file, before
+/// they are checked and turned into [`Settings`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Options {
+    pub work_time: Duration,
+    pub short_wait_time: Duration,
+    pub long_wait_time: Duration,
+    pub cycles: u32,
+    pub dark_mode: bool,
+    pub adjust_step: Duration,
+    pub overtime: bool,
+    pub confirm_transitions: bool,
+    /// Replaces the classic schedule built from the times and cycles above.
+    pub schedule: Option<Schedule>,
+    pub sessions: Option<u32>,
+    pub until: Option<TimeOfDay>,
+    pub flowtime: bool,
+    pub flow_breaks: FlowBreaks,
+    pub keys: KeyMap,
+    pub confirm_actions: Vec<Action>,
+    pub after_void: AfterVoid,
+    pub reflect: bool,
+    /// Pomodoros to aim for each day, 0 for no goal.
+    pub daily_goal: u32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            work_time: Duration::from_secs(25 * 60),
+            short_wait_time: Duration::from_secs(5 * 60),
+            long_wait_time: Duration::from_secs(20 * 60),
+            cycles: 4,
+            dark_mode: false,
+            adjust_step: Duration::from_secs(5 * 60),
+            overtime: false,
+            confirm_transitions: false,
+            schedule: None,
+            sessions: None,
+            until: None,
+            flowtime: false,
+            flow_breaks: FlowBreaks::default(),
+            keys: KeyMap::default(),
+            confirm_actions: vec![Action::Reset, Action::Quit],
+            after_void: AfterVoid::default(),
+            reflect: false,
+            daily_goal: 0,
+        }
+    }
+}
+
+impl Options {
+    /// Checks every option and returns all problems found.
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+        for (option, duration) in [
+            ("work_time", self.work_time),
+            ("short_wait_time", self.short_wait_time),
+            ("long_wait_time", self.long_wait_time),
+            ("adjust_step", self.adjust_step),
+        ] {
+            if duration.is_zero() {
+                errors.push(SettingsError::ZeroDuration { option });
+            }
+        }
+        if self.cycles == 0 {
+            errors.push(SettingsError::ZeroCycles);
+        }
+        if let Some(schedule) = &self.schedule {
+            if schedule.phases.is_empty() {
+                errors.push(SettingsError::EmptySchedule);
+            }
+            for (index, phase) in schedule.phases.iter().enumerate() {
+                if phase.duration.is_zero() {
+                    errors.push(SettingsError::ZeroPhase {
+                        index,
+                        name: phase.name.clone(),
+                    });
+                }
+            }
+            if !schedule.phases.is_empty() && schedule.work_phases() == 0 {
+                errors.push(SettingsError::NoWorkPhase);
+            }
+        }
+        if self.sessions == Some(0) {
+            errors.push(SettingsError::ZeroSessions);
+        }
+        match &self.flow_breaks {
+            FlowBreaks::Ratio(0) => errors.push(SettingsError::ZeroFlowRatio),
+            FlowBreaks::Ratio(_) => {}
+            FlowBreaks::Table(rules) => {
+                if rules.is_empty() {
+                    errors.push(SettingsError::EmptyFlowBreaks);
+                }
+                let mut previous = None;
+                for (index, (up_to, _)) in rules.iter().enumerate() {
+                    match up_to {
+                        None if index + 1 < rules.len() => {
+                            errors.push(SettingsError::FlowCatchAllNotLast)
+                        }
+                        Some(up_to) if previous.is_some_and(|previous| *up_to <= previous) => {
+                            errors.push(SettingsError::FlowBreaksUnordered { index })
+                        }
+                        _ => {}
+                    }
+                    previous = *up_to;
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Validates the options and builds [`Settings`], resolving `until` to the
+    /// next such time after `now`.
+    pub fn into_settings(self, now: SystemTime) -> Result<Settings, ValidationErrors> {
+        self.validate()?;
+        let mut settings = Settings::new(
+            self.work_time,
+            self.short_wait_time,
+            self.long_wait_time,
+            self.cycles,
+            self.dark_mode,
+        );
+        if let Some(schedule) = self.schedule {
+            settings.schedule = schedule;
+        }
+        settings.adjust_step = self.adjust_step;
+        settings.overtime = self.overtime;
+        settings.confirm_transitions = self.confirm_transitions;
+        settings.sessions = self.sessions;
+        settings.until = self.until.map(|until| until.next_after(now));
+        settings.flowtime = self.flowtime;
+        settings.flow_breaks = self.flow_breaks;
+        settings.keys = self.keys;
+        settings.confirm_actions = self.confirm_actions;
+        settings.after_void = self.after_void;
+        settings.reflect = self.reflect;
+        settings.daily_goal = (self.daily_goal > 0).then_some(self.daily_goal);
+        Ok(settings)
+    }
+}
+
+/// Options from one source such as a config file or the command line. Fields
+/// left as `None` keep the value from the layer below.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct OptionsPatch {
+    pub work_time: Option<Duration>,
+    pub short_wait_time: Option<Duration>,
+    pub long_wait_time: Option<Duration>,
+    pub cycles: Option<u32>,
+    pub dark_mode: Option<bool>,
+    pub adjust_step: Option<Duration>,
+    pub overtime: Option<bool>,
+    pub confirm_transitions: Option<bool>,
+    pub schedule: Option<Schedule>,
+    pub sessions: Option<u32>,
+    pub until: Option<TimeOfDay>,
+    pub flowtime: Option<bool>,
+    pub flow_breaks: Option<FlowBreaks>,
+    pub confirm_actions: Option<Vec<Action>>,
+    pub after_void: Option<AfterVoid>,
+    pub reflect: Option<bool>,
+    pub daily_goal: Option<u32>,
+}
+
+impl OptionsPatch {
+    /// Overwrites the options that are set in this patch.
+    pub fn apply(&self, options: &mut Options) {
+        fn set<T: Clone>(option: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *option = value.clone();
+            }
+        }
+        set(&mut options.work_time, &self.work_time);
+        set(&mut options.short_wait_time, &self.short_wait_time);
+        set(&mut options.long_wait_time, &self.long_wait_time);
+        set(&mut options.cycles, &self.cycles);
+        set(&mut options.dark_mode, &self.dark_mode);
+        set(&mut options.adjust_step, &self.adjust_step);
+        set(&mut options.overtime, &self.overtime);
+        set(&mut options.confirm_transitions, &self.confirm_transitions);
+        if self.schedule.is_some() {
+            options.schedule = self.schedule.clone();
+        }
+        if self.sessions.is_some() {
+            options.sessions = self.sessions;
+        }
+        if self.until.is_some() {
+            options.until = self.until;
+        }
+        set(&mut options.flowtime, &self.flowtime);
+        set(&mut options.flow_breaks, &self.flow_breaks);
+        set(&mut options.confirm_actions, &self.confirm_actions);
+        set(&mut options.after_void, &self.after_void);
+        set(&mut options.reflect, &self.reflect);
+        set(&mut options.daily_goal, &self.daily_goal);
+    }
+}
+
+/// A problem with one of the [`Options`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SettingsError {
+    ZeroDuration { option: &'static str },
+    ZeroCycles,
+    EmptySchedule,
+    ZeroPhase { index: usize, name: String },
+    NoWorkPhase,
+    ZeroSessions,
+    ZeroFlowRatio,
+    EmptyFlowBreaks,
+    FlowCatchAllNotLast,
+    FlowBreaksUnordered { index: usize },
+}
+
+impl SettingsError {
+    /// The option at fault, named as in the config file.
+    pub fn option(&self) -> &'static str {
+        match self {
+            SettingsError::ZeroDuration { option } => option,
+            SettingsError::ZeroCycles => "cycles",
+            SettingsError::EmptySchedule
+            | SettingsError::ZeroPhase { .. }
+            | SettingsError::NoWorkPhase => "schedule",
+            SettingsError::ZeroSessions => "sessions",
+            SettingsError::ZeroFlowRatio
+            | SettingsError::EmptyFlowBreaks
+            | SettingsError::FlowCatchAllNotLast
+            | SettingsError::FlowBreaksUnordered { .. } => "flow_breaks",
+        }
+    }
+
+    /// How to fix it.
+    pub fn suggestion(&self) -> &'static str {
+        match self {
+            SettingsError::ZeroDuration { .. } => "use a duration like 25m, 90s or 1h15m",
+            SettingsError::ZeroCycles => "use at least 1 cycle, the default is 4",
+            SettingsError::EmptySchedule => "list phases like \"work 25, break 5\"",
+            SettingsError::ZeroPhase { .. } => "give every phase a duration like 25m",
+            SettingsError::NoWorkPhase => "add a phase like \"work 25\"",
+            SettingsError::ZeroSessions => "use at least 1 session or leave the option out",
+            SettingsError::ZeroFlowRatio => "use a ratio of at least 1, e.g. /5",
+            SettingsError::EmptyFlowBreaks => "use e.g. \"25m=5m, 50m=8m, 15m\"",
+            SettingsError::FlowCatchAllNotLast => {
+                "put the entry without a work time last, e.g. \"25m=5m, 15m\""
+            }
+            SettingsError::FlowBreaksUnordered { .. } => {
+                "list the work times from shortest to longest"
+            }
+        }
+    }
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::ZeroDuration { .. } => write!(f, "must be greater than zero"),
+            SettingsError::ZeroCycles => write!(f, "must be at least 1"),
+            SettingsError::EmptySchedule => write!(f, "has no phases"),
+            SettingsError::ZeroPhase { index, name } => {
+                write!(f, "phase {} ({name}) has no duration", index + 1)
+            }
+            SettingsError::NoWorkPhase => write!(f, "has no work phase"),
+            SettingsError::ZeroSessions => write!(f, "must be at least 1"),
+            SettingsError::ZeroFlowRatio => write!(f, "ratio must be at least 1"),
+            SettingsError::EmptyFlowBreaks => write!(f, "has no entries"),
+            SettingsError::FlowCatchAllNotLast => {
+                write!(f, "can only leave out the work time on its last entry")
+            }
+            SettingsError::FlowBreaksUnordered { index } => {
+                write!(f, "entry {} is not longer than the one before", index + 1)
+            }
+        }
+    }
+}
+
+/// Every problem found by [`Options::validate`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ValidationErrors(pub Vec<SettingsError>);
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "'{}' {}\n  help: {}",
+                error.option(),
+                error,
+                error.suggestion()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationErrors {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Phase, PhaseKind};
+
+    fn errors(options: Options) -> Vec<SettingsError> {
+        options.validate().unwrap_err().0
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(Options::default().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_zero_durations() {
+        let options = Options {
+            work_time: Duration::ZERO,
+            adjust_step: Duration::ZERO,
+            ..Options::default()
+        };
+        assert_eq!(
+            errors(options),
+            vec![
+                SettingsError::ZeroDuration {
+                    option: "work_time"
+                },
+                SettingsError::ZeroDuration {
+                    option: "adjust_step"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_zero_cycles() {
+        let options = Options {
+            cycles: 0,
+            ..Options::default()
+        };
+        assert_eq!(errors(options), vec![SettingsError::ZeroCycles]);
+    }
+
+    #[test]
+    fn rejects_empty_schedule() {
+        let options = Options {
+            schedule: Some(Schedule { phases: vec![] }),
+            ..Options::default()
+        };
+        assert_eq!(errors(options), vec![SettingsError::EmptySchedule]);
+    }
+
+    #[test]
+    fn rejects_zero_phase() {
+        let options = Options {
+            schedule: Some(Schedule {
+                phases: vec![Phase::new("Work", PhaseKind::Work, Duration::ZERO)],
+            }),
+            ..Options::default()
+        };
+        assert_eq!(
+            errors(options),
+            vec![SettingsError::ZeroPhase {
+                index: 0,
+                name: "Work".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_schedule_without_work() {
+        let options = Options {
+            schedule: Some("break 5, break 10".parse().unwrap()),
+            ..Options::default()
+        };
+        assert_eq!(errors(options), vec![SettingsError::NoWorkPhase]);
+    }
+
+    #[test]
+    fn rejects_zero_sessions() {
+        let options = Options {
+            sessions: Some(0),
+            ..Options::default()
+        };
+        assert_eq!(errors(options), vec![SettingsError::ZeroSessions]);
+    }
+
+    #[test]
+    fn rejects_zero_flow_ratio() {
+        let options = Options {
+            flow_breaks: FlowBreaks::Ratio(0),
+            ..Options::default()
+        };
+        assert_eq!(errors(options), vec![SettingsError::ZeroFlowRatio]);
+    }
+
+    #[test]
+    fn rejects_empty_flow_breaks() {
+        let options = Options {
+            flow_breaks: FlowBreaks::Table(vec![]),
+            ..Options::default()
+        };
+        assert_eq!(errors(options), vec![SettingsError::EmptyFlowBreaks]);
+    }
+
+    #[test]
+    fn rejects_flow_catch_all_before_end() {
+        let options = Options {
+            flow_breaks: "15m, 25m=5m".parse().unwrap(),
+            ..Options::default()
+        };
+        assert_eq!(errors(options), vec![SettingsError::FlowCatchAllNotLast]);
+    }
+
+    #[test]
+    fn rejects_unordered_flow_breaks() {
+        let options = Options {
+            flow_breaks: "50m=8m, 25m=5m".parse().unwrap(),
+            ..Options::default()
+        };
+        assert_eq!(
+            errors(options),
+            vec![SettingsError::FlowBreaksUnordered { index: 1 }]
+        );
+    }
+
+    #[test]
+    fn zero_daily_goal_is_no_goal() {
+        let mut options = Options::default();
+        OptionsPatch {
+            daily_goal: Some(0),
+            ..OptionsPatch::default()
+        }
+        .apply(&mut options);
+        let now = SystemTime::UNIX_EPOCH;
+        assert_eq!(options.clone().into_settings(now).unwrap().daily_goal, None);
+        options.daily_goal = 10;
+        assert_eq!(options.into_settings(now).unwrap().daily_goal, Some(10));
+    }
+}
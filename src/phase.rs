@@ -0,0 +1,211 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use tui::style::Color;
+
+use crate::{format_duration, parse_duration};
+
+/// Whether a phase is focused work or a break.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PhaseKind {
+    Work,
+    Break,
+}
+
+/// One step of a [`Schedule`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Phase {
+    pub name: String,
+    pub kind: PhaseKind,
+    pub duration: Duration,
+    /// Gauge colour, `None` uses the default for the kind.
+    pub color: Option<Color>,
+}
+
+impl Phase {
+    pub fn new(name: impl Into<String>, kind: PhaseKind, duration: Duration) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            duration,
+            color: None,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
+
+/// The phases the timer runs through, starting over after the last one.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Schedule {
+    pub phases: Vec<Phase>,
+}
+
+impl Schedule {
+    /// Work → short break repeated `work_cycles - 1` times, then work → long
+    /// break.
+    pub fn classic(
+        work_time: Duration,
+        short_wait_time: Duration,
+        long_wait_time: Duration,
+        work_cycles: u32,
+    ) -> Self {
+        let mut phases = Vec::new();
+        for cycle in 1..=work_cycles {
+            phases.push(Phase::new("Work", PhaseKind::Work, work_time));
+            if cycle < work_cycles {
+                phases.push(
+                    Phase::new("Short break", PhaseKind::Break, short_wait_time)
+                        .with_color(Color::LightBlue),
+                );
+            } else {
+                phases.push(
+                    Phase::new("Long break", PhaseKind::Break, long_wait_time)
+                        .with_color(Color::LightGreen),
+                );
+            }
+        }
+        Self { phases }
+    }
+
+    /// Number of work phases in the schedule.
+    pub fn work_phases(&self) -> usize {
+        self.phases
+            .iter()
+            .filter(|phase| phase.kind == PhaseKind::Work)
+            .count()
+    }
+}
+
+/// Parses a comma separated list of `KIND[:NAME] DURATION [COLOUR]` entries,
+/// e.g. `work 50, break 10, work:Deep_work 1h30m magenta, break 30`. `KIND`
+/// is `work` or `break`, underscores in `NAME` are shown as spaces and the
+/// duration is read by [`parse_duration`].
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let phases = s
+            .split(',')
+            .map(parse_phase)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { phases })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, phase) in self.phases.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            let (kind, default_name) = match phase.kind {
+                PhaseKind::Work => ("work", "Work"),
+                PhaseKind::Break => ("break", "Break"),
+            };
+            write!(f, "{}", kind)?;
+            if phase.name != default_name {
+                write!(f, ":{}", phase.name.replace(' ', "_"))?;
+            }
+            write!(f, " {}", format_duration(phase.duration))?;
+            if let Some(color) = phase.color {
+                write!(f, " {}", format_color(color))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_phase(entry: &str) -> Result<Phase, String> {
+    let mut words = entry.split_whitespace();
+    let Some(head) = words.next() else {
+        return Err("empty phase in schedule".into());
+    };
+    let (kind, name) = match head.split_once(':') {
+        Some((kind, name)) => (kind, Some(name.replace('_', " "))),
+        None => (head, None),
+    };
+    let (kind, default_name) = match kind.to_lowercase().as_str() {
+        "work" => (PhaseKind::Work, "Work"),
+        "break" => (PhaseKind::Break, "Break"),
+        _ => {
+            return Err(format!(
+                "unknown phase kind '{kind}' in '{}', expected 'work' or 'break'",
+                entry.trim()
+            ))
+        }
+    };
+    let duration = words
+        .next()
+        .ok_or_else(|| format!("missing duration in '{}'", entry.trim()))?;
+    let duration = parse_duration(duration)?;
+    let mut phase = Phase::new(name.unwrap_or_else(|| default_name.into()), kind, duration);
+    if let Some(color) = words.next() {
+        phase.color = Some(parse_color(color)?);
+    }
+    if let Some(extra) = words.next() {
+        return Err(format!("unexpected '{extra}' in '{}'", entry.trim()));
+    }
+    Ok(phase)
+}
+
+/// Parses a colour name such as `lightblue` or a `#rrggbb` hex code.
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| format!("invalid hex colour '{s}'"))
+        };
+        if hex.len() != 6 {
+            return Err(format!("invalid hex colour '{s}'"));
+        }
+        return Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    let color = match s.to_lowercase().replace(['_', '-'], "").as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return Err(format!("unknown colour '{s}'")),
+    };
+    Ok(color)
+}
+
+/// Inverse of [`parse_color`].
+pub fn format_color(color: Color) -> String {
+    match color {
+        Color::Black => "black".into(),
+        Color::Red => "red".into(),
+        Color::Green => "green".into(),
+        Color::Yellow => "yellow".into(),
+        Color::Blue => "blue".into(),
+        Color::Magenta => "magenta".into(),
+        Color::Cyan => "cyan".into(),
+        Color::Gray => "gray".into(),
+        Color::DarkGray => "darkgray".into(),
+        Color::LightRed => "lightred".into(),
+        Color::LightGreen => "lightgreen".into(),
+        Color::LightYellow => "lightyellow".into(),
+        Color::LightBlue => "lightblue".into(),
+        Color::LightMagenta => "lightmagenta".into(),
+        Color::LightCyan => "lightcyan".into(),
+        Color::White => "white".into(),
+        Color::Rgb(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+        Color::Indexed(_) | Color::Reset => "gray".into(),
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+use crate::{config::unknown, Action, FlowBreaks, KeyMap, Schedule};
+
+/// Timer configuration.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Settings {
+    /// Name of the config profile these settings came from.
+    pub profile: Option<String>,
+    /// Phases to run through, see [`Schedule::classic`] for the default.
+    pub schedule: Schedule,
+    pub dark_mode: bool,
+    /// How much `App::extend` and `App::shorten` change the running phase.
+    pub adjust_step: Duration,
+    /// Keep running past the end of a phase until `App::confirm` is called.
+    pub overtime: bool,
+    /// Wait in `PomoState::Pending` for `App::confirm` between phases.
+    pub confirm_transitions: bool,
+    /// Stop after this many completed work phases.
+    pub sessions: Option<u32>,
+    /// Don't start new phases after this time.
+    pub until: Option<SystemTime>,
+    /// Run work phases as `PomoState::Flow`, counting up until ended.
+    pub flowtime: bool,
+    /// Length of the break after a flowtime work phase.
+    pub flow_breaks: FlowBreaks,
+    pub keys: KeyMap,
+    /// Actions that ask before going ahead while a phase is active.
+    pub confirm_actions: Vec<Action>,
+    /// Where `App::void` goes after voiding a work phase.
+    pub after_void: AfterVoid,
+    /// Ask what got done after each completed work phase.
+    pub reflect: bool,
+    /// Completed work phases to aim for each day, see
+    /// `App::daily_progress`.
+    pub daily_goal: Option<u32>,
+}
+
+/// What happens after a work phase is voided.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AfterVoid {
+    /// Back to the menu, ending the session.
+    #[default]
+    Menu,
+    /// Start the same work phase over.
+    Work,
+}
+
+impl FromStr for AfterVoid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "menu" => Ok(AfterVoid::Menu),
+            "work" => Ok(AfterVoid::Work),
+            s => Err(unknown("value", s, &["menu", "work"])),
+        }
+    }
+}
+
+impl fmt::Display for AfterVoid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AfterVoid::Menu => "menu",
+            AfterVoid::Work => "work",
+        })
+    }
+}
+
+impl Settings {
+    /// Builds settings for the classic schedule.
+    pub fn new(
+        work_time: Duration,
+        short_wait_time: Duration,
+        long_wait_time: Duration,
+        work_cycles: u32,
+        dark_mode: bool,
+    ) -> Self {
+        Self {
+            profile: None,
+            schedule: Schedule::classic(work_time, short_wait_time, long_wait_time, work_cycles),
+            dark_mode,
+            adjust_step: Duration::from_secs(5 * 60),
+            overtime: false,
+            confirm_transitions: false,
+            sessions: None,
+            until: None,
+            flowtime: false,
+            flow_breaks: FlowBreaks::default(),
+            keys: KeyMap::default(),
+            confirm_actions: vec![Action::Reset, Action::Quit],
+            after_void: AfterVoid::default(),
+            reflect: false,
+            daily_goal: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Hash of the settings that shape a session, to tell whether a saved
+    /// session was run with the same schedule. Keys, `dark_mode` and `until`
+    /// (a point in time rather than a setting of the schedule) are left out.
+    pub fn fingerprint(&self) -> u64 {
+        let shape = format!(
+            "{:?}",
+            (
+                &self.schedule,
+                self.overtime,
+                self.confirm_transitions,
+                self.sessions,
+                self.flowtime,
+                &self.flow_breaks,
+            )
+        );
+        // FNV-1a, stable across builds unlike `DefaultHasher`.
+        shape.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new(
+            Duration::from_secs(25 * 60),
+            Duration::from_secs(5 * 60),
+            Duration::from_secs(20 * 60),
+            4,
+            false,
+        )
+    }
+}
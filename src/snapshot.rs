@@ -0,0 +1,249 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    files::{write_atomic, xdg_dir},
+    format_color,
+    history::{interruption_to_json, interruptions_from_json, kind_name, parse_kind},
+    json::{self, Json},
+    localtime::{format_rfc3339, parse_rfc3339},
+    parse_color, Interruption, Phase, PomoState,
+};
+
+/// Version of the session file format, files from another version are
+/// ignored.
+const VERSION: u64 = 1;
+
+/// Everything needed to pick up a session where it was left, see
+/// [`App::snapshot`](crate::App::snapshot) and
+/// [`App::restore`](crate::App::restore).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Snapshot {
+    pub saved_at: SystemTime,
+    /// [`Settings::fingerprint`](crate::Settings::fingerprint) of the
+    /// settings the session ran with.
+    pub fingerprint: u64,
+    pub state: PomoState,
+    /// Full length of the running phase, including adjustments.
+    pub phase_time: i64,
+    pub cycle: Option<usize>,
+    pub paused: bool,
+    pub phase_started: SystemTime,
+    pub paused_time: Duration,
+    pub interruptions: Vec<Interruption>,
+    pub pomodoros: u32,
+    pub focus_time: Duration,
+}
+
+impl Snapshot {
+    /// `$XDG_STATE_HOME/pomotui/session.json`, falling back to
+    /// `~/.local/state/pomotui/session.json`.
+    pub fn default_path() -> Option<PathBuf> {
+        xdg_dir("XDG_STATE_HOME", ".local/state")
+            .map(|dir| dir.join("pomotui").join("session.json"))
+    }
+
+    /// Writes the snapshot to `path`, replacing the previous one in a single
+    /// step so a crash never leaves half a file behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, &(self.to_json() + "\n"))
+    }
+
+    /// Reads the snapshot at `path`, `None` if there is none or it can't be
+    /// used.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(src) => Ok(Self::from_json(src.trim()).ok()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Deletes the snapshot at `path`, if any.
+    pub fn remove(path: &Path) -> io::Result<()> {
+        match fs::remove_file(path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let millis = |duration: Duration| Json::from(duration.as_millis() as u64);
+        Json::Object(vec![
+            ("v".into(), VERSION.into()),
+            ("saved_at".into(), format_rfc3339(self.saved_at).into()),
+            (
+                "fingerprint".into(),
+                format!("{:016x}", self.fingerprint).into(),
+            ),
+            ("state".into(), state_to_json(&self.state)),
+            ("phase_time_ms".into(), self.phase_time.into()),
+            ("cycle".into(), self.cycle.map(|cycle| cycle as u64).into()),
+            ("paused".into(), self.paused.into()),
+            (
+                "phase_started".into(),
+                format_rfc3339(self.phase_started).into(),
+            ),
+            ("paused_ms".into(), millis(self.paused_time)),
+            (
+                "interruptions".into(),
+                Json::Array(
+                    self.interruptions
+                        .iter()
+                        .map(interruption_to_json)
+                        .collect(),
+                ),
+            ),
+            ("pomodoros".into(), u64::from(self.pomodoros).into()),
+            ("focus_ms".into(), millis(self.focus_time)),
+        ])
+        .to_string()
+    }
+
+    fn from_json(src: &str) -> Result<Self, String> {
+        let json = json::parse(src)?;
+        if json.get("v").and_then(Json::as_u64) != Some(VERSION) {
+            return Err("unsupported session file version".into());
+        }
+        let field = |key: &str| json.get(key).ok_or_else(|| format!("missing '{key}'"));
+        let string = |key: &str| field(key)?.as_str().ok_or_else(|| invalid(key));
+        let number = |key: &str| field(key)?.as_u64().ok_or_else(|| invalid(key));
+        Ok(Self {
+            saved_at: parse_rfc3339(string("saved_at")?)?,
+            fingerprint: u64::from_str_radix(string("fingerprint")?, 16)
+                .map_err(|_| invalid("fingerprint"))?,
+            state: state_from_json(field("state")?)?,
+            phase_time: field("phase_time_ms")?
+                .as_i64()
+                .ok_or_else(|| invalid("phase_time_ms"))?,
+            cycle: match field("cycle")? {
+                Json::Null => None,
+                _ => Some(number("cycle")? as usize),
+            },
+            paused: field("paused")?
+                .as_bool()
+                .ok_or_else(|| invalid("paused"))?,
+            phase_started: parse_rfc3339(string("phase_started")?)?,
+            paused_time: Duration::from_millis(number("paused_ms")?),
+            interruptions: interruptions_from_json(json.get("interruptions"))?,
+            pomodoros: number("pomodoros")? as u32,
+            focus_time: Duration::from_millis(number("focus_ms")?),
+        })
+    }
+}
+
+fn invalid(key: &str) -> String {
+    format!("invalid '{key}'")
+}
+
+fn state_to_json(state: &PomoState) -> Json {
+    let (state, mut members) = match state {
+        PomoState::Menu => ("menu", vec![]),
+        PomoState::Running { phase, time_left } => (
+            "running",
+            vec![
+                ("phase".into(), phase_to_json(phase)),
+                ("time_left_ms".into(), (*time_left).into()),
+            ],
+        ),
+        PomoState::Flow { phase, elapsed } => (
+            "flow",
+            vec![
+                ("phase".into(), phase_to_json(phase)),
+                ("elapsed_ms".into(), (elapsed.as_millis() as u64).into()),
+            ],
+        ),
+        PomoState::Pending { next } => ("pending", vec![("next".into(), phase_to_json(next))]),
+        PomoState::Complete => ("complete", vec![]),
+    };
+    members.insert(0, ("type".into(), state.into()));
+    Json::Object(members)
+}
+
+fn state_from_json(json: &Json) -> Result<PomoState, String> {
+    let field = |key: &str| json.get(key).ok_or_else(|| format!("missing '{key}'"));
+    let state = field("type")?.as_str().ok_or_else(|| invalid("type"))?;
+    Ok(match state {
+        "menu" => PomoState::Menu,
+        "running" => PomoState::Running {
+            phase: phase_from_json(field("phase")?)?,
+            time_left: field("time_left_ms")?
+                .as_i64()
+                .ok_or_else(|| invalid("time_left_ms"))?,
+        },
+        "flow" => PomoState::Flow {
+            phase: phase_from_json(field("phase")?)?,
+            elapsed: Duration::from_millis(
+                field("elapsed_ms")?
+                    .as_u64()
+                    .ok_or_else(|| invalid("elapsed_ms"))?,
+            ),
+        },
+        "pending" => PomoState::Pending {
+            next: phase_from_json(field("next")?)?,
+        },
+        "complete" => PomoState::Complete,
+        state => return Err(format!("unknown state '{state}'")),
+    })
+}
+
+fn phase_to_json(phase: &Phase) -> Json {
+    Json::Object(vec![
+        ("name".into(), phase.name.as_str().into()),
+        ("kind".into(), kind_name(phase.kind).into()),
+        (
+            "duration_ms".into(),
+            (phase.duration.as_millis() as u64).into(),
+        ),
+        ("color".into(), phase.color.map(format_color).into()),
+    ])
+}
+
+fn phase_from_json(json: &Json) -> Result<Phase, String> {
+    let field = |key: &str| json.get(key).ok_or_else(|| format!("missing '{key}'"));
+    let string = |key: &str| field(key)?.as_str().ok_or_else(|| invalid(key));
+    let duration = field("duration_ms")?
+        .as_u64()
+        .ok_or_else(|| invalid("duration_ms"))?;
+    let mut phase = Phase::new(
+        string("name")?,
+        parse_kind(string("kind")?)?,
+        Duration::from_millis(duration),
+    );
+    if let Json::String(color) = field("color")? {
+        phase = phase.with_color(parse_color(color)?);
+    }
+    Ok(phase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{localtime::from_unix_seconds, PhaseKind};
+    use tui::style::Color;
+
+    #[test]
+    fn snapshots_round_trip() {
+        let snapshot = Snapshot {
+            saved_at: from_unix_seconds(1_700_000_000),
+            fingerprint: u64::MAX - 1,
+            state: PomoState::Running {
+                phase: Phase::new("Long break", PhaseKind::Break, Duration::from_secs(1200))
+                    .with_color(Color::LightGreen),
+                time_left: -1500,
+            },
+            phase_time: 1_201_000,
+            cycle: Some(7),
+            paused: true,
+            phase_started: from_unix_seconds(1_699_998_000),
+            paused_time: Duration::from_millis(2500),
+            interruptions: Vec::new(),
+            pomodoros: 4,
+            focus_time: Duration::from_secs(6000),
+        };
+        assert_eq!(Snapshot::from_json(&snapshot.to_json()), Ok(snapshot));
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::{Duration, SystemTime};
+
+use crate::Phase;
+
+/// Phase the timer is currently in. `time_left` is kept in signed
+/// milliseconds as it goes negative once a phase runs into overtime.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PomoState {
+    Menu,
+    /// A phase of the schedule is counting down.
+    Running {
+        phase: Phase,
+        time_left: i64,
+    },
+    /// A flowtime work phase counting up until the user ends it.
+    Flow {
+        phase: Phase,
+        elapsed: Duration,
+    },
+    /// A phase has ended and `next` starts once confirmed.
+    Pending {
+        next: Phase,
+    },
+    /// The session target in `Settings` has been reached.
+    Complete,
+}
+
+impl PomoState {
+    /// Starts `phase` with its full duration left.
+    pub fn running(phase: Phase) -> Self {
+        PomoState::Running {
+            time_left: phase.duration.as_millis() as i64,
+            phase,
+        }
+    }
+
+    /// Time left in the phase, `None` when no phase is running.
+    pub fn get_inner(&self) -> Option<i64> {
+        match self {
+            PomoState::Menu
+            | PomoState::Flow { .. }
+            | PomoState::Pending { .. }
+            | PomoState::Complete => None,
+            PomoState::Running { time_left, .. } => Some(*time_left),
+        }
+    }
+
+    /// Same phase with a different time left, states without a timer are
+    /// returned unchanged.
+    pub fn with_inner(&self, time_left: i64) -> Self {
+        match self {
+            PomoState::Menu
+            | PomoState::Flow { .. }
+            | PomoState::Pending { .. }
+            | PomoState::Complete => self.clone(),
+            PomoState::Running { phase, .. } => PomoState::Running {
+                phase: phase.clone(),
+                time_left,
+            },
+        }
+    }
+
+    /// The running phase, if any.
+    pub fn phase(&self) -> Option<&Phase> {
+        match self {
+            PomoState::Running { phase, .. } | PomoState::Flow { phase, .. } => Some(phase),
+            _ => None,
+        }
+    }
+
+    /// Whether a phase is running or about to, as opposed to sitting in the
+    /// menu or on the summary screen.
+    pub fn is_active(&self) -> bool {
+        !matches!(self, PomoState::Menu | PomoState::Complete)
+    }
+
+    /// Human readable name of the phase.
+    pub fn name(&self) -> &str {
+        match self {
+            PomoState::Menu => "Menu",
+            PomoState::Running { phase, .. } | PomoState::Flow { phase, .. } => &phase.name,
+            PomoState::Pending { .. } => "Waiting",
+            PomoState::Complete => "Session complete",
+        }
+    }
+}
+
+/// How a phase ended.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PhaseOutcome {
+    /// The timer ran out.
+    Completed,
+    /// The user skipped ahead with [`App::skip`](crate::App::skip).
+    Skipped,
+    /// The session was restarted or quit part way through the phase, see
+    /// [`App::abandon`](crate::App::abandon).
+    Abandoned,
+    /// The work phase was called off and doesn't count, see
+    /// [`App::void`](crate::App::void).
+    Voided,
+}
+
+/// Where an interruption of a work phase came from.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum InterruptionKind {
+    /// Your own urge to do something else, marked `'`.
+    Internal,
+    /// Someone or something else, marked `-`.
+    External,
+}
+
+/// An interruption logged with [`App::interrupt`](crate::App::interrupt).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Interruption {
+    pub kind: InterruptionKind,
+    pub at: SystemTime,
+    pub note: Option<String>,
+}
+
+/// A phase that has ended, as reported by
+/// [`App::drain_transitions`](crate::App::drain_transitions).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Transition {
+    /// The phase as it was when it ended.
+    pub from: PomoState,
+    pub outcome: PhaseOutcome,
+    /// Wall-clock time the phase started at.
+    pub started: SystemTime,
+    pub ended: SystemTime,
+    /// Length of the phase in the schedule, `None` for flowtime phases which
+    /// have no set length.
+    pub planned: Option<Duration>,
+    /// Time the phase actually ran for, not counting pauses.
+    pub actual: Duration,
+    /// Time spent paused during the phase.
+    pub paused: Duration,
+    /// Time spent past the end of the phase.
+    pub overtime: Duration,
+    /// The active task when a work phase ended.
+    pub task: Option<String>,
+    pub interruptions: Vec<Interruption>,
+    /// Why a voided phase was voided.
+    pub reason: Option<String>,
+}
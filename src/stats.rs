@@ -0,0 +1,212 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{json::Json, localtime::Date, InterruptionKind, PhaseKind, PhaseOutcome, Record};
+
+/// Totals over the session history, see [`Stats::new`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Stats {
+    /// Completed work phases.
+    pub pomodoros: u32,
+    pub today: u32,
+    /// Pomodoros since Monday.
+    pub this_week: u32,
+    /// Pomodoros and time focused per day, oldest first and ending today,
+    /// including days without any.
+    pub days: Vec<Day>,
+    /// Time spent in work phases, completed or not, leaving out voided ones.
+    pub focus_time: Duration,
+    /// How much longer than planned breaks ran on average, `None` without
+    /// any breaks.
+    pub average_break_overrun: Option<Duration>,
+    /// Most days in a row with at least one pomodoro.
+    pub longest_streak: u32,
+    pub internal_interruptions: u32,
+    pub external_interruptions: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Day {
+    pub date: Date,
+    pub pomodoros: u32,
+    pub focus_time: Duration,
+    pub interruptions: u32,
+}
+
+impl Stats {
+    /// Stats for `records`, with `days` days of daily totals up to `today`.
+    /// Records count towards the local day they started on.
+    pub fn new(records: &[Record], today: Date, days: usize) -> Self {
+        let mut per_day: BTreeMap<Date, Day> = BTreeMap::new();
+        let mut overruns = Vec::new();
+        let (mut internal, mut external) = (0, 0);
+        for record in records {
+            internal += record.interruption_count(InterruptionKind::Internal);
+            external += record.interruption_count(InterruptionKind::External);
+            let date = Date::local(record.started);
+            match record.kind {
+                PhaseKind::Work => {
+                    let day = per_day.entry(date).or_insert(Day {
+                        date,
+                        pomodoros: 0,
+                        focus_time: Duration::ZERO,
+                        interruptions: 0,
+                    });
+                    // A voided pomodoro doesn't count at all.
+                    if record.outcome != PhaseOutcome::Voided {
+                        day.focus_time += record.actual;
+                    }
+                    day.interruptions += record.interruptions.len() as u32;
+                    if record.outcome == PhaseOutcome::Completed {
+                        day.pomodoros += 1;
+                    }
+                }
+                PhaseKind::Break => {
+                    if let Some(planned) = record.planned {
+                        overruns.push(record.actual.saturating_sub(planned));
+                    }
+                }
+            }
+        }
+
+        let today_days = today.days();
+        // 1970-01-01 was a Thursday.
+        let monday = today_days - (today_days + 3).rem_euclid(7);
+        let days = (0..days as i64)
+            .rev()
+            .map(|ago| {
+                let date = Date::from_days(today_days - ago);
+                per_day.get(&date).copied().unwrap_or(Day {
+                    date,
+                    pomodoros: 0,
+                    focus_time: Duration::ZERO,
+                    interruptions: 0,
+                })
+            })
+            .collect();
+
+        let mut longest_streak = 0;
+        let mut streak = 0;
+        let mut previous: Option<i64> = None;
+        for day in per_day.values().filter(|day| day.pomodoros > 0) {
+            let days = day.date.days();
+            streak = if previous == Some(days - 1) {
+                streak + 1
+            } else {
+                1
+            };
+            longest_streak = longest_streak.max(streak);
+            previous = Some(days);
+        }
+
+        Self {
+            pomodoros: per_day.values().map(|day| day.pomodoros).sum(),
+            today: per_day.get(&today).map_or(0, |day| day.pomodoros),
+            this_week: per_day
+                .values()
+                .filter(|day| (monday..=today_days).contains(&day.date.days()))
+                .map(|day| day.pomodoros)
+                .sum(),
+            days,
+            focus_time: per_day.values().map(|day| day.focus_time).sum(),
+            average_break_overrun: (!overruns.is_empty())
+                .then(|| overruns.iter().sum::<Duration>() / overruns.len() as u32),
+            longest_streak,
+            internal_interruptions: internal,
+            external_interruptions: external,
+        }
+    }
+
+    /// The stats as a JSON object, durations in seconds.
+    pub fn to_json(&self) -> String {
+        let secs = |duration: Duration| Json::from(duration.as_secs());
+        let days = self
+            .days
+            .iter()
+            .map(|day| {
+                Json::Object(vec![
+                    ("date".into(), day.date.to_string().into()),
+                    ("pomodoros".into(), u64::from(day.pomodoros).into()),
+                    ("focus_secs".into(), secs(day.focus_time)),
+                    ("interruptions".into(), u64::from(day.interruptions).into()),
+                ])
+            })
+            .collect();
+        Json::Object(vec![
+            ("pomodoros".into(), u64::from(self.pomodoros).into()),
+            ("today".into(), u64::from(self.today).into()),
+            ("this_week".into(), u64::from(self.this_week).into()),
+            ("focus_secs".into(), secs(self.focus_time)),
+            (
+                "average_break_overrun_secs".into(),
+                self.average_break_overrun.map(|d| d.as_secs()).into(),
+            ),
+            (
+                "longest_streak".into(),
+                u64::from(self.longest_streak).into(),
+            ),
+            (
+                "internal_interruptions".into(),
+                u64::from(self.internal_interruptions).into(),
+            ),
+            (
+                "external_interruptions".into(),
+                u64::from(self.external_interruptions).into(),
+            ),
+            ("days".into(), Json::Array(days)),
+        ])
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(date: &str, kind: PhaseKind, outcome: PhaseOutcome, actual: u64) -> Record {
+        let date: Date = date.parse().unwrap();
+        let started = date.start() + Duration::from_secs(9 * 3600);
+        Record {
+            kind,
+            planned: Some(Duration::from_secs(300)),
+            outcome,
+            ..Record::sample(started, actual)
+        }
+    }
+
+    #[test]
+    fn counts_days_weeks_and_streaks() {
+        use PhaseKind::*;
+        use PhaseOutcome::*;
+        let mut records = [
+            record("2024-04-26", Work, Completed, 1500),
+            record("2024-04-28", Work, Completed, 1500),
+            record("2024-04-29", Work, Completed, 1500),
+            record("2024-04-29", Break, Completed, 400),
+            record("2024-04-30", Work, Completed, 1500),
+            record("2024-04-30", Work, Abandoned, 600),
+            record("2024-04-30", Break, Skipped, 100),
+            record("2024-05-01", Work, Completed, 1500),
+            record("2024-05-01", Work, Voided, 900),
+        ];
+        records[1].interruptions = vec![crate::Interruption {
+            kind: InterruptionKind::External,
+            at: records[1].started,
+            note: None,
+        }];
+        // A Wednesday.
+        let stats = Stats::new(&records, "2024-05-01".parse().unwrap(), 7);
+        assert_eq!(stats.pomodoros, 5);
+        assert_eq!(stats.today, 1);
+        assert_eq!(stats.this_week, 3);
+        assert_eq!(stats.longest_streak, 4);
+        assert_eq!(stats.focus_time, Duration::from_secs(5 * 1500 + 600));
+        assert_eq!(stats.average_break_overrun, Some(Duration::from_secs(50)));
+        assert_eq!(
+            (stats.internal_interruptions, stats.external_interruptions),
+            (0, 1)
+        );
+        let per_day: Vec<u32> = stats.days.iter().map(|day| day.pomodoros).collect();
+        assert_eq!(per_day, [0, 1, 0, 1, 1, 1, 1]);
+        assert_eq!(stats.days[6].date.to_string(), "2024-05-01");
+    }
+}
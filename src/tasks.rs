@@ -0,0 +1,216 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    files::write_atomic,
+    history::data_dir,
+    json::{self, Json},
+};
+
+/// Version of the task file format.
+const VERSION: u64 = 1;
+
+/// Something to work on, with the pomodoros spent on it so far.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Task {
+    pub name: String,
+    /// Pomodoros the task is expected to take.
+    pub estimate: Option<u32>,
+    /// Completed work phases attributed to the task.
+    pub pomodoros: u32,
+    pub done: bool,
+}
+
+impl Task {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            estimate: None,
+            pomodoros: 0,
+            done: false,
+        }
+    }
+}
+
+/// The tasks and which one completed work phases count towards, see
+/// [`App::tasks`](crate::App::tasks).
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TaskList {
+    tasks: Vec<Task>,
+    active: Option<usize>,
+}
+
+impl TaskList {
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    /// Index of the active task.
+    pub fn active_index(&self) -> Option<usize> {
+        self.active
+    }
+
+    pub fn active(&self) -> Option<&Task> {
+        self.tasks.get(self.active?)
+    }
+
+    pub fn add(&mut self, task: Task) {
+        self.tasks.push(task);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Task> {
+        if index >= self.tasks.len() {
+            return None;
+        }
+        self.active = match self.active {
+            Some(active) if active == index => None,
+            Some(active) if active > index => Some(active - 1),
+            active => active,
+        };
+        Some(self.tasks.remove(index))
+    }
+
+    /// Makes the task at `index` the active one, or clears it with `None`.
+    /// Tasks that are done can't be made active.
+    pub fn set_active(&mut self, index: Option<usize>) {
+        self.active = index.filter(|&i| self.tasks.get(i).is_some_and(|task| !task.done));
+    }
+
+    /// Marks the task at `index` done or not done, a task that is done stops
+    /// being the active one.
+    pub fn set_done(&mut self, index: usize, done: bool) {
+        let Some(task) = self.tasks.get_mut(index) else {
+            return;
+        };
+        task.done = done;
+        if done && self.active == Some(index) {
+            self.active = None;
+        }
+    }
+
+    /// Changes the estimate of the task at `index` by `delta` pomodoros,
+    /// clearing it when it drops to zero.
+    pub fn adjust_estimate(&mut self, index: usize, delta: i32) {
+        if let Some(task) = self.tasks.get_mut(index) {
+            let estimate = task.estimate.unwrap_or(0).saturating_add_signed(delta);
+            task.estimate = (estimate > 0).then_some(estimate);
+        }
+    }
+
+    /// Counts a completed work phase towards the active task.
+    pub(crate) fn attribute(&mut self) {
+        if let Some(task) = self.active.and_then(|i| self.tasks.get_mut(i)) {
+            task.pomodoros += 1;
+        }
+    }
+
+    /// `$XDG_DATA_HOME/pomotui/tasks.json`, falling back to
+    /// `~/.local/share/pomotui/tasks.json`.
+    pub fn default_path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join("pomotui").join("tasks.json"))
+    }
+
+    /// Reads the task file at `path`, a missing file is an empty list.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(src) => Self::from_json(src.trim()).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid task file: {error}"),
+                )
+            }),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Writes the task file, replacing the previous one in a single step.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, &(self.to_json() + "\n"))
+    }
+
+    fn to_json(&self) -> String {
+        let tasks = self
+            .tasks
+            .iter()
+            .map(|task| {
+                Json::Object(vec![
+                    ("name".into(), task.name.as_str().into()),
+                    ("estimate".into(), task.estimate.map(u64::from).into()),
+                    ("pomodoros".into(), u64::from(task.pomodoros).into()),
+                    ("done".into(), task.done.into()),
+                ])
+            })
+            .collect();
+        Json::Object(vec![
+            ("v".into(), VERSION.into()),
+            ("active".into(), self.active.map(|i| i as u64).into()),
+            ("tasks".into(), Json::Array(tasks)),
+        ])
+        .to_string()
+    }
+
+    fn from_json(src: &str) -> Result<Self, String> {
+        let json = json::parse(src)?;
+        if json.get("v").and_then(Json::as_u64) != Some(VERSION) {
+            return Err("unsupported version".into());
+        }
+        let Some(Json::Array(items)) = json.get("tasks") else {
+            return Err("missing 'tasks'".into());
+        };
+        let mut tasks = Vec::new();
+        for item in items {
+            let field = |key: &str| item.get(key).ok_or_else(|| format!("missing '{key}'"));
+            let number = |key: &str| {
+                field(key)?
+                    .as_u64()
+                    .map(|n| n as u32)
+                    .ok_or_else(|| format!("invalid '{key}'"))
+            };
+            tasks.push(Task {
+                name: field("name")?.as_str().ok_or("invalid 'name'")?.to_string(),
+                estimate: match field("estimate")? {
+                    Json::Null => None,
+                    _ => Some(number("estimate")?),
+                },
+                pomodoros: number("pomodoros")?,
+                done: field("done")?.as_bool().ok_or("invalid 'done'")?,
+            });
+        }
+        let active = json
+            .get("active")
+            .and_then(Json::as_u64)
+            .map(|i| i as usize)
+            .filter(|&i| i < tasks.len());
+        Ok(Self { tasks, active })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_active_task_and_round_trips() {
+        let mut list = TaskList::default();
+        for name in ["Write report", "Review \"PR\"", "Inbox"] {
+            list.add(Task::new(name));
+        }
+        list.set_active(Some(2));
+        list.attribute();
+        list.remove(0);
+        assert_eq!(list.active().map(|task| task.name.as_str()), Some("Inbox"));
+        assert_eq!(list.active().map(|task| task.pomodoros), Some(1));
+        list.adjust_estimate(0, 2);
+        list.adjust_estimate(0, -1);
+        assert_eq!(list.tasks()[0].estimate, Some(1));
+        assert_eq!(TaskList::from_json(&list.to_json()), Ok(list.clone()));
+
+        list.set_done(1, true);
+        assert_eq!(list.active(), None);
+        list.set_active(Some(1));
+        assert_eq!(list.active(), None);
+    }
+}
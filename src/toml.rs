@@ -0,0 +1,412 @@
+//! Just enough TOML for pomotui's config files: tables, string, integer and
+//! boolean values and arrays of them.
+
+use std::fmt;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "an integer",
+            Value::Boolean(_) => "a boolean",
+            Value::Array(_) => "an array",
+        }
+    }
+}
+
+/// A `key = value` line together with the table it appeared in.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Entry {
+    /// Header segments of the enclosing table, empty at the top level.
+    pub(crate) table: Vec<String>,
+    pub(crate) key: String,
+    pub(crate) value: Value,
+    pub(crate) line: usize,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct ParseError {
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+pub(crate) fn parse(src: &str) -> Result<Vec<Entry>, ParseError> {
+    let mut cursor = Cursor::new(src);
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut table = Vec::new();
+    // Headers seen so far and their lines.
+    let mut tables: Vec<(Vec<String>, usize)> = Vec::new();
+    loop {
+        cursor.skip_blank();
+        if cursor.rest.is_empty() {
+            return Ok(entries);
+        }
+        let line = cursor.line;
+        if cursor.eat('[') {
+            table = cursor.path(']').map_err(|e| cursor.error(e))?;
+            if !cursor.eat(']') {
+                return Err(cursor.error("expected ']' after table name".into()));
+            }
+            if let Some((_, first)) = tables.iter().find(|(other, _)| *other == table) {
+                return Err(cursor.error(format!(
+                    "table [{}] is already defined on line {first}",
+                    table.join(".")
+                )));
+            }
+            tables.push((table.clone(), line));
+        } else {
+            let mut key = cursor.path('=').map_err(|e| cursor.error(e))?;
+            if key.len() != 1 {
+                return Err(cursor.error("dotted keys are not supported".into()));
+            }
+            let key = key.remove(0);
+            if !cursor.eat('=') {
+                return Err(cursor.error("expected '=' after key".into()));
+            }
+            if let Some(first) = entries
+                .iter()
+                .find(|entry| entry.table == table && entry.key == key)
+            {
+                return Err(cursor.error(format!("'{key}' is already set on line {}", first.line)));
+            }
+            cursor.skip_space();
+            let value = cursor.value().map_err(|e| cursor.error(e))?;
+            entries.push(Entry {
+                table: table.clone(),
+                key,
+                value,
+                line,
+            });
+        }
+        cursor.skip_space();
+        if !cursor.eat_line_end() {
+            return Err(cursor.error("unexpected text at end of line".into()));
+        }
+    }
+}
+
+/// `s` as a basic string.
+pub(crate) fn quote(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+struct Cursor<'a> {
+    rest: &'a str,
+    /// Line of the start of `rest`, counting from 1.
+    line: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { rest: src, line: 1 }
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: self.line,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Skips spaces up to the end of the line.
+    fn skip_space(&mut self) {
+        self.rest = self.rest.trim_start_matches([' ', '\t', '\r']);
+    }
+
+    /// Skips a comment and the line break after it, `false` unless at the end
+    /// of a line.
+    fn eat_line_end(&mut self) -> bool {
+        if self.rest.starts_with('#') {
+            self.rest = &self.rest[self.rest.find('\n').unwrap_or(self.rest.len())..];
+        }
+        if self.eat('\n') {
+            self.line += 1;
+            true
+        } else {
+            self.rest.is_empty()
+        }
+    }
+
+    /// Skips spaces, comments and line breaks, e.g. between array items.
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_space();
+            if self.rest.is_empty() || !self.eat_line_end() {
+                return;
+            }
+        }
+    }
+
+    /// Dot separated bare or quoted names up to `end`.
+    fn path(&mut self, end: char) -> Result<Vec<String>, String> {
+        let mut segments = Vec::new();
+        loop {
+            self.skip_space();
+            let segment = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let len = self
+                        .rest
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                        .unwrap_or(self.rest.len());
+                    if len == 0 {
+                        return Err(format!("expected a name before '{end}'"));
+                    }
+                    let (segment, rest) = self.rest.split_at(len);
+                    self.rest = rest;
+                    segment.to_string()
+                }
+            };
+            segments.push(segment);
+            self.skip_space();
+            if !self.eat('.') {
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => {
+                self.eat('[');
+                let mut values = Vec::new();
+                loop {
+                    self.skip_blank();
+                    if self.eat(']') {
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(self.value()?);
+                    self.skip_blank();
+                    if !self.eat(',') {
+                        return if self.eat(']') {
+                            Ok(Value::Array(values))
+                        } else {
+                            Err("expected ',' or ']' in array".into())
+                        };
+                    }
+                }
+            }
+            _ => {
+                let len = self
+                    .rest
+                    .find(|c: char| c.is_whitespace() || c == ',' || c == ']' || c == '#')
+                    .unwrap_or(self.rest.len());
+                let (word, rest) = self.rest.split_at(len);
+                self.rest = rest;
+                match word {
+                    "" => Err("expected a value".into()),
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ => word
+                        .replace('_', "")
+                        .parse()
+                        .map(Value::Integer)
+                        .map_err(|_| format!("invalid value '{word}', strings need quotes")),
+                }
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.eat('"');
+        let mut s = String::new();
+        let mut chars = self.rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[i + 1..];
+                    return Ok(s);
+                }
+                '\n' => break,
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, '"')) => s.push('"'),
+                    Some((_, '\\')) => s.push('\\'),
+                    Some((_, c)) => return Err(format!("unsupported escape '\\{c}'")),
+                    None => break,
+                },
+                c => s.push(c),
+            }
+        }
+        Err("unterminated string".into())
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.eat('\'');
+        match self.rest.split_once('\'') {
+            Some((s, rest)) if !s.contains('\n') => {
+                self.rest = rest;
+                Ok(s.to_string())
+            }
+            _ => Err("unterminated string".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(src: &str) -> Vec<(Vec<String>, String, Value)> {
+        parse(src)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.table, entry.key, entry.value))
+            .collect()
+    }
+
+    fn error(src: &str) -> String {
+        parse(src).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_values_tables_and_comments() {
+        let src = r#"
+# a comment
+cycles = 1_000 # trailing comment
+dark_mode = true
+step = -5
+name = "a \"b\"\t# not a comment\\"
+path = 'C:\no\escapes'
+
+[profiles."deep work"]
+keys = ["n", 'g n',]
+empty = []
+"#;
+        let profile = vec!["profiles".to_string(), "deep work".to_string()];
+        assert_eq!(
+            values(src),
+            vec![
+                (vec![], "cycles".into(), Value::Integer(1000)),
+                (vec![], "dark_mode".into(), Value::Boolean(true)),
+                (vec![], "step".into(), Value::Integer(-5)),
+                (
+                    vec![],
+                    "name".into(),
+                    Value::String("a \"b\"\t# not a comment\\".into())
+                ),
+                (
+                    vec![],
+                    "path".into(),
+                    Value::String(r"C:\no\escapes".into())
+                ),
+                (
+                    profile.clone(),
+                    "keys".into(),
+                    Value::Array(vec![Value::String("n".into()), Value::String("g n".into())])
+                ),
+                (profile, "empty".into(), Value::Array(vec![])),
+            ]
+        );
+        assert_eq!(parse("\n\nkey = 1").unwrap()[0].line, 3);
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        assert_eq!(
+            error("a = 1\nb = word"),
+            "line 2: invalid value 'word', strings need quotes"
+        );
+        assert_eq!(error("a = \"open"), "line 1: unterminated string");
+        assert_eq!(error("a = \"\\q\""), "line 1: unsupported escape '\\q'");
+        assert_eq!(error("a = [1, 2"), "line 1: expected ',' or ']' in array");
+        assert_eq!(error("[keys"), "line 1: expected ']' after table name");
+        assert_eq!(error("a.b = 1"), "line 1: dotted keys are not supported");
+        assert_eq!(error("a 1"), "line 1: expected '=' after key");
+        assert_eq!(error("a = 1 2"), "line 1: unexpected text at end of line");
+    }
+
+    #[test]
+    fn arrays_span_lines() {
+        let src = "quit = [\n  \"q\", # the default\n\n  \"ctrl+c\",\n]\nskip = \"n\"";
+        let entries = parse(src).unwrap();
+        assert_eq!(
+            entries[0].value,
+            Value::Array(vec![
+                Value::String("q".into()),
+                Value::String("ctrl+c".into())
+            ])
+        );
+        assert_eq!((entries[0].line, entries[1].line), (1, 6));
+        assert_eq!(
+            error("a = [\n 1,\n 2 3\n]"),
+            "line 3: expected ',' or ']' in array"
+        );
+        assert_eq!(error("a = [\n\"open\n]"), "line 2: unterminated string");
+        assert_eq!(error("a = [1,\n"), "line 2: expected a value");
+        assert_eq!(error("a =\nb = 1"), "line 1: expected a value");
+    }
+
+    #[test]
+    fn rejects_duplicates() {
+        assert_eq!(
+            error("cycles = 4\n# later\ncycles = 5"),
+            "line 3: 'cycles' is already set on line 1"
+        );
+        assert_eq!(
+            error("[profiles.a]\ncycles = 4\n[profiles.b]\n[profiles.a]"),
+            "line 4: table [profiles.a] is already defined on line 1"
+        );
+        assert_eq!(
+            error("[keys]\nquit = \"q\"\n\"quit\" = \"x\""),
+            "line 3: 'quit' is already set on line 2"
+        );
+        // The same key in different tables is fine.
+        assert_eq!(
+            parse("cycles = 4\n[profiles.a]\ncycles = 2\n[profiles.b]\ncycles = 3")
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn quoted_strings_read_back() {
+        let s = "tab\there \"quoted\" back\\slash\nline";
+        let src = format!("s = {}", quote(s));
+        assert_eq!(values(&src)[0].2, Value::String(s.into()));
+    }
+}